@@ -14,6 +14,7 @@ use std::sync::atomic::{AtomicU8, Ordering};
 enum Event {
     Log(Log),
     TestGELog(usize, Arc<AtomicU8>), // 0 = not done, 1 = done + false, 2 = done + true
+    SetLogLimit(usize),
 
     EnableBar(String, ProgressBarKind),
     ProgressBarSet(f64),
@@ -52,6 +53,8 @@ const SHARK: &str = "|\\";
 
 fn thread_loop(rx: mpsc::Receiver<Event>) {
     let mut logs: Vec<Log> = Vec::new();
+    let mut hidden: Vec<usize> = Vec::new();
+    let mut limit: usize = usize::MAX;
     let mut bar: Option<ProgressBar> = None;
     let mut log_bar: String = String::new();
 
@@ -98,6 +101,11 @@ fn thread_loop(rx: mpsc::Receiver<Event>) {
     loop {
         match rx.recv().expect("Failed to receive event") {
             Event::Log(log) => {
+                if log.id >= limit {
+                    hidden.push(log.id);
+                    continue;
+                }
+
                 let i = logs.iter().rposition(|l| l.id < log.id).map_or(0, |i| i + 1);
 
                 let offset = offset(&logs, bar.is_some(), &log_bar);
@@ -107,12 +115,14 @@ fn thread_loop(rx: mpsc::Receiver<Event>) {
             },
 
             Event::TestGELog(id, out) => {
-                if logs.iter().any(|log| log.id <= id) {
+                if logs.iter().any(|log| log.id <= id) || hidden.iter().any(|h| *h <= id) {
                     out.fetch_add(1, Ordering::Relaxed);
                 }
                 out.fetch_add(1, Ordering::Relaxed);
             },
 
+            Event::SetLogLimit(l) => limit = l,
+
 
             Event::EnableBar(msg, kind) => {
                 bar = Some(ProgressBar { 
@@ -231,6 +241,13 @@ impl LogHandler {
         self.tx.send(event).expect("Failed to send Log event");
     }
 
+    // Logs with an id of `limit` or above are no longer drawn,
+    // they still count towards `test_ge_log` though.
+    pub fn set_log_limit(&self, limit: usize) {
+        self.tx.send(Event::SetLogLimit(limit))
+            .expect("Failed to send SetLogLimit event");
+    }
+
     pub fn test_ge_log(&self, id: usize) -> bool {
         let out = Arc::new(AtomicU8::new(0));
         self.tx.send(Event::TestGELog(id, out.clone()))
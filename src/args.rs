@@ -29,6 +29,11 @@ impl<T> Arg<T> {
             error!("'{}' may only be used once", name);
         }
         self.value = value;
+        self.set = true;
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set
     }
 }
 
@@ -52,6 +57,7 @@ pub struct Args {
     pub output:       Arg<&'static str>,
     pub debug:        Arg<bool>,
     pub code_context: Arg<bool>,
+    pub stack_usage:  Arg<bool>,
     pub level:        Arg<Level>,
     pub verbs:        Vec<&'static str>,
 }
@@ -63,6 +69,7 @@ impl Args {
             output:       Arg::new("main.asm"),
             debug:        Arg::new(false),
             code_context: Arg::new(true),
+            stack_usage:  Arg::new(false),
             level:        Arg::new(Level::Warn),
             verbs:        Vec::new(),
         }
@@ -135,6 +142,7 @@ impl Args {
                     });
                 },
                 "--no-context" => self.code_context.try_mut(arg, false),
+                "--stack-usage" => self.stack_usage.try_mut(arg, true),
 
                 _ => {
                     error!("unrecognized argument {arg}");
@@ -185,9 +193,10 @@ const HELP_MESSAGE: &str = "\x1b[1mDESCRIPTION\x1b[0m
     -f, --file FILE             File to compile
         (default: main.shd)
     -o, --output FILE           File to write to
-        (default: main.asm)
+        (default: NAME.asm for a `:name NAME` tag, otherwise main.asm)
 
-        --no-context            Disable code context";
+        --no-context            Disable code context
+        --stack-usage           Print the stack usage of every label";
 const SHARK_ASCII: &str = r#"                                 ,-
                                ,'::|
                               /::::|
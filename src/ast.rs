@@ -7,9 +7,21 @@ use crate::token::TokenKind;
 pub struct Program {
     pub filename: &'static str,
     pub stmts:    Vec<AST>,
+    pub metadata: ProgramMetadata,
 }
 
-#[derive(Debug, PartialEq)]
+// Whatever the top level tags say about the program as a whole
+#[derive(Debug, Default)]
+pub struct ProgramMetadata {
+    // `:name "..."`, what the output is called unless one is given on the command line
+    pub name:   Option<(Span, String)>,
+    // `:arch ...`, every architecture the program can be built for
+    pub arch:   Option<(Span, Vec<String>)>,
+    // tags the compiler doesn't know about, left for other tools
+    pub custom: Vec<(String, Vec<AST>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Operator {
     // PREFIX
     MutatePre,
@@ -57,6 +69,8 @@ pub enum Operator {
     Thread, // clojure threading operator
 
     Access,
+    // `a[i]` or `a.N`, the element at that index
+    Index,
     InternalCall,
     ExternalCall,
 
@@ -92,6 +106,35 @@ impl Operator {
         })
     }
 
+    // How the operator is spelled when used infix
+    pub fn infix_symbol(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Sequence => ";",
+            Self::Or => "||",
+            Self::Xor => "^^",
+            Self::And => "&&",
+            Self::Eq => "=",
+            Self::Neq => "~=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::ShiftL => "<<",
+            Self::ShiftR => ">>",
+            Self::Add => "+",
+            Self::Substract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Modulo => "%",
+            Self::AddressOf => "&",
+            Self::Thread => "=>",
+            Self::Assign => "<-",
+            Self::Cast => "->",
+            Self::Access => ".",
+            _ => return None,
+        })
+    }
+
     pub fn from_infix(kind: TokenKind) -> Result<Self, ()> {
         Ok(match kind {
             TokenKind::Semicolon => Self::Sequence,
@@ -123,19 +166,51 @@ impl Operator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ASTKind {
     // Definitions
     LabelDefinition(Option<String>, Vec<LabelAttribute>),
-    FunctionDefinition(String, Vec<LabelAttribute>, Box<AST>),
+    FunctionDefinition(
+        String,
+        Vec<TypeParameter>,
+        Vec<LabelAttribute>,
+        Vec<Parameter>,
+        Option<Type>,
+        Box<AST>,
+    ),
+    VariableDefinition(String, Type, bool, Option<Box<AST>>),
+    StructDefinition(String, Vec<StructField>),
+    EnumDefinition(String, Type, Vec<EnumVariant>),
+    TypeAlias(String, Type),
+    Destructure(Vec<DestructureBinding>, Box<AST>),
+    // a symbol defined outside the program, called with `@`
+    ExternDeclaration(String, Vec<Parameter>, Option<Type>),
+    // the routine's label, chosen by the parser as operators have no name
+    OperatorDefinition(String, Operator, Vec<Parameter>, Type, Box<AST>),
+
+    // `:name args...`, only allowed at the top level
+    Tag(String, Vec<AST>),
 
     // Keywords
     Return(Option<Box<AST>>),
 
+    // Control flow
+    // `cond ? then : else`, each branch is a block or an expression
+    Conditional(Box<AST>, Box<AST>, Option<Box<AST>>),
+    // `loop [name:] [cond] { ... }`, runs until `cond` fails or forever without one
+    Loop(Option<String>, Option<Box<AST>>, Box<AST>),
+    // the loop to leave or restart, the innermost one if it has no name
+    Break(Option<String>),
+    Continue(Option<String>),
+    // `match value { pat, pat => body | ... }`, exhaustive once every value has an arm
+    Match(Box<AST>, Vec<MatchArm>, bool),
+
     // Expressions
     BinaryExpr(Operator, Box<AST>, Box<AST>),
     UnaryExpr(Operator, Box<AST>),
     Identifier(String),
+    // a generic function along with what its type parameters stand for, filled in by the checker
+    Instance(String, Vec<Type>),
 
     IntegerLiteral(usize),
     StringLiteral(String),
@@ -145,6 +220,12 @@ pub enum ASTKind {
     Block(Vec<AST>),
 
     TypeAnnotation(Type, Box<AST>),
+    // `$T`, the size of a type in bytes
+    SizeOf(Type),
+    // `x -> T`, or `cast T x` when explicit
+    Cast(Type, Box<AST>, bool),
+    // `a[lo..hi]`, either end may be left out, lowered into a pointer and length by the checker
+    Slice(Box<AST>, Option<Box<AST>>, Option<Box<AST>>),
 
     // Calls
     Interrupt(usize),
@@ -152,19 +233,249 @@ pub enum ASTKind {
     Call(Box<AST>, Vec<AST>, bool),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StructField {
+    pub name: String,
+    pub ty:   Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>,
+    pub body:     AST,
+    pub span:     Span,
+    // what the patterns evaluate to as inclusive ranges of unsigned values, filled in by the checker
+    pub ranges:   Vec<(usize, usize)>,
+}
+
+// Besides `_` these are constant expressions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    // `_`, anything no other arm matches
+    Wildcard(Span),
+    Value(AST),
+    // `lo..hi`, including both ends
+    Range(Box<AST>, Box<AST>),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Wildcard(span) => *span,
+            Self::Value(value) => value.span,
+            Self::Range(lo, hi) => lo.span.extend(&hi.span),
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wildcard(_) => write!(f, "_"),
+            Self::Value(value) => write!(f, "{value}"),
+            Self::Range(lo, hi) => write!(f, "{lo}..{hi}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumVariant {
+    pub name:  String,
+    // one more than the previous variant when left out
+    pub value: Option<AST>,
+    pub span:  Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DestructureBinding {
+    pub name:       String,
+    // taken from the destructured value by the checker when left out
+    pub ty:         Option<Type>,
+    pub is_mutable: bool,
+    pub span:       Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Parameter {
+    pub name: String,
+    pub ty:   Type,
+    pub span: Span,
+}
+
+impl Parameter {
+    // Anything too big for a register is passed by reference
+    pub fn local_type(&self) -> Type {
+        match self.ty.fits_register() {
+            true => self.ty.clone(),
+            false => Type::Heap { is_pointer: true, contents: vec![self.ty.clone()] },
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.ty)
+    }
+}
+
+// `<T, U>` after a function's name, each one stands for a type given by the caller
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeParameter {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LabelAttribute {
     Entry,
+    // `export` or `global`
+    Export,
+    Inline,
+    NoReturn,
+    Align(usize),
+    Section(String),
+}
+
+impl LabelAttribute {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Entry => "entry",
+            Self::Export => "export",
+            Self::Inline => "inline",
+            Self::NoReturn => "noreturn",
+            Self::Align(_) => "align",
+            Self::Section(_) => "section",
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+pub const POINTER_SIZE: usize = 8;
+// a function returning a heap hands back each of its elements in a register of its own
+pub const RETURN_VALUES: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Size(usize),
-    Heap { is_pointer: bool, contents: Vec<Type> },
-    // NOTE: a size of 0 represents an array of undetermined length e.g [1:]
-    Array { inner: Box<Type>, elems: Option<usize> },
-    Struct(String),
-    Register { inner: Option<Box<Type>>, ident: usize },
+    Heap { is_pointer: bool, contents: Vec<Self> },
+    // NOTE: no length represents an array of undetermined length e.g [1:]
+    Array { inner: Box<Self>, elems: Option<ArrayLength> },
+    // the size is filled in by the checker once the definition is found
+    Struct(String, Option<usize>),
+    Register { inner: Option<Box<Self>>, ident: usize },
+    // a named type after the checker has expanded it
+    Alias(String, Box<Self>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArrayLength {
+    Literal(usize),
+    // a constant expression, along with its value once the checker has evaluated it
+    Expr(Box<AST>, Option<usize>),
+}
+
+impl ArrayLength {
+    pub fn value(&self) -> Option<usize> {
+        match self {
+            Self::Literal(len) => Some(*len),
+            Self::Expr(_, len) => *len,
+        }
+    }
+}
+
+impl Type {
+    // None for anything that can't be sized on its own, e.g. `1:` or structs
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Self::Size(s) => Some(*s),
+            Self::Heap { is_pointer: true, .. } => Some(POINTER_SIZE),
+            Self::Heap { contents, .. } => contents.iter().map(Self::size).sum(),
+            Self::Array { inner, elems: Some(elems) } =>
+                inner.size().zip(elems.value()).map(|(s, elems)| s * elems),
+            Self::Struct(_, size) => *size,
+            Self::Array { elems: None, .. } => None,
+            Self::Register { inner, .. } => inner.as_ref().map_or(Some(POINTER_SIZE), |t| t.size()),
+            Self::Alias(_, ty) => ty.size(),
+        }
+    }
+
+    // Whether a value of this type can be held in a general purpose register
+    pub fn fits_register(&self) -> bool {
+        match self.expanded() {
+            Self::Size(size) => *size <= POINTER_SIZE,
+            Self::Heap { is_pointer: true, .. } | Self::Register { .. } => true,
+            _ => false,
+        }
+    }
+
+    // The elements of a heap returned by value, each is handed back in a register of its own.
+    // The checker makes sure they fit in one.
+    pub fn return_values(&self) -> Option<&[Self]> {
+        match self.expanded() {
+            Self::Heap { is_pointer: false, contents } if contents.len() > 1 => Some(contents),
+            _ => None,
+        }
+    }
+
+    // Looks through any aliases to the type underneath
+    pub fn expanded(&self) -> &Self {
+        match self {
+            Self::Alias(_, ty) => ty.expanded(),
+            ty => ty,
+        }
+    }
+
+    // The type found by dereferencing a value of this type
+    pub fn pointee(&self) -> Option<Self> {
+        match self {
+            Self::Heap { is_pointer: true, contents } if contents.len() == 1 =>
+                Some(contents[0].clone()),
+            Self::Heap { is_pointer: true, contents } =>
+                Some(Self::Heap { is_pointer: false, contents: contents.clone() }),
+            Self::Register { inner: Some(inner), .. } | Self::Alias(_, inner) => inner.pointee(),
+            _ => None,
+        }
+    }
+
+    // Replaces the type parameters in `substitution` with the types they stand for
+    pub fn substitute(&self, substitution: &[(String, Self)]) -> Self {
+        match self {
+            Self::Struct(name, None) => substitution
+                .iter()
+                .find(|(param, _)| param == name)
+                .map_or_else(|| self.clone(), |(_, ty)| ty.clone()),
+            Self::Heap { is_pointer, contents } => Self::Heap {
+                is_pointer: *is_pointer,
+                contents:   contents.iter().map(|ty| ty.substitute(substitution)).collect(),
+            },
+            Self::Array { inner, elems } => Self::Array {
+                inner: Box::new(inner.substitute(substitution)),
+                elems: elems.clone(),
+            },
+            Self::Register { inner, ident } => Self::Register {
+                inner: inner.as_ref().map(|inner| Box::new(inner.substitute(substitution))),
+                ident: *ident,
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    // The type of each element when indexing a value of this type, along with the number of
+    // elements if that's known and whether they're reached through a pointer
+    pub fn indexed(&self) -> Option<(Self, Option<usize>, bool)> {
+        match self {
+            Self::Array { inner, elems } =>
+                Some(((**inner).clone(), elems.as_ref().and_then(ArrayLength::value), false)),
+            Self::Heap { is_pointer: true, contents } if contents.len() == 1 =>
+                match contents[0].expanded() {
+                    Self::Array { inner, elems } =>
+                        Some(((**inner).clone(), elems.as_ref().and_then(ArrayLength::value), true)),
+                    ty => Some((ty.clone(), None, true)),
+                },
+            Self::Register { inner: Some(inner), .. } | Self::Alias(_, inner) => inner.indexed(),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Type {
@@ -172,14 +483,12 @@ impl Display for Type {
         match self {
             Self::Size(s) => write!(f, "{s}")?,
             Self::Array { inner, elems } => {
-                write!(f, "{inner}")?;
-                if elems.is_none() {
-                    return Ok(());
-                };
-
-                write!(f, ":")?;
-                if elems.unwrap() != 0 {
-                    write!(f, "{}", elems.unwrap())?;
+                write!(f, "{inner}:")?;
+                match elems {
+                    Some(ArrayLength::Literal(len) | ArrayLength::Expr(_, Some(len))) =>
+                        write!(f, "{len}")?,
+                    Some(ArrayLength::Expr(expr, None)) => write!(f, "{expr}")?,
+                    None => {},
                 }
             },
             Self::Heap { is_pointer, contents } => {
@@ -199,8 +508,11 @@ impl Display for Type {
                 }
                 write!(f, ";r{ident}")?;
             },
-            Self::Struct(ident) => write!(f, "{ident}")?,
-        };
+            Self::Struct(ident, _) => write!(f, "{ident}")?,
+            // `{:#}` also shows what the alias stands for
+            Self::Alias(name, ty) if f.alternate() => write!(f, "{name} (= {ty:#})")?,
+            Self::Alias(name, _) => write!(f, "{name}")?,
+        }
         Ok(())
     }
 }
@@ -211,7 +523,7 @@ impl ASTKind {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AST {
     pub span: Span,
     pub kind: ASTKind,
@@ -221,6 +533,41 @@ impl AST {
     pub fn new(span: Span, kind: ASTKind) -> Self {
         Self { span, kind }
     }
+
+    // Conservative, anything which could write memory, jump, or define something counts.
+    pub fn has_side_effects(&self) -> bool {
+        match &self.kind {
+            ASTKind::BinaryExpr(Operator::Assign, ..)
+            | ASTKind::UnaryExpr(
+                Operator::MutatePre
+                | Operator::MutatePost
+                | Operator::Increment
+                | Operator::Decrement,
+                _,
+            ) => true,
+
+            ASTKind::BinaryExpr(_, lhs, rhs) => lhs.has_side_effects() || rhs.has_side_effects(),
+            ASTKind::UnaryExpr(_, operand)
+            | ASTKind::TypeAnnotation(_, operand)
+            | ASTKind::Cast(_, operand, _) => operand.has_side_effects(),
+            ASTKind::HeapLiteral(values) => values.iter().any(Self::has_side_effects),
+            ASTKind::Conditional(cond, then, otherwise) =>
+                cond.has_side_effects()
+                    || then.has_side_effects()
+                    || otherwise.as_ref().is_some_and(|otherwise| otherwise.has_side_effects()),
+            ASTKind::Match(value, arms, _) =>
+                value.has_side_effects() || arms.iter().any(|arm| arm.body.has_side_effects()),
+
+            ASTKind::Identifier(_)
+            | ASTKind::Instance(..)
+            | ASTKind::IntegerLiteral(_)
+            | ASTKind::StringLiteral(_)
+            | ASTKind::CharLiteral(_)
+            | ASTKind::SizeOf(_) => false,
+
+            _ => true,
+        }
+    }
 }
 
 impl Display for AST {
@@ -228,57 +575,163 @@ impl Display for AST {
         match &self.kind {
             ASTKind::IntegerLiteral(val) => write!(f, "(IntegerLiteral: {val})")?,
             ASTKind::BinaryExpr(op, lhs, rhs) => write!(f, "(BinaryExpr {op:?} {lhs} {rhs})")?,
+            ASTKind::Slice(base, lo, hi) => {
+                write!(f, "(Slice: {base} ")?;
+                if let Some(lo) = lo {
+                    write!(f, "{lo}")?;
+                }
+                write!(f, "..")?;
+                if let Some(hi) = hi {
+                    write!(f, "{hi}")?;
+                }
+                write!(f, ")")?;
+            },
             ASTKind::UnaryExpr(op, operand) => write!(f, "(UnaryExpr {op:?} {operand})")?,
             ASTKind::Identifier(ident) => write!(f, "(Identifier: {ident})")?,
+            ASTKind::Instance(name, types) => {
+                let types = types.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "(Instance: {name}<{}>)", types.join(", "))?;
+            },
             ASTKind::Block(stmts) => write!(f, "(Block: {} statements)", stmts.len())?,
             ASTKind::StringLiteral(val) => write!(f, "(StringLiteral: {val:?})")?,
             ASTKind::CharLiteral(val) => write!(f, "(CharLiteral: {val:?})")?,
             ASTKind::HeapLiteral(values) => {
                 write!(f, "(HeapLiteral {{ ")?;
-                values.into_iter().fold(Ok(()), |_, v| write!(f, "{v} "))?;
+                values.iter().try_for_each(|v| write!(f, "{v} "))?;
                 write!(f, "}})")?;
             },
             ASTKind::TypeAnnotation(ty, ast) => write!(f, "(TypeAnnotation: {ty} {ast})")?,
-            ASTKind::LabelDefinition(Some(name), attrs) => write!(
-                f,
-                "(LabelDefinition: {name} ({}))",
-                attrs.iter().fold(String::new(), |mut acc, attr| {
-                    acc.push_str(&format!("{attr:?} "));
-                    acc
-                })
-            )?,
-            ASTKind::LabelDefinition(_, attrs) => write!(
-                f,
-                "(LabelDefinition: ({}))",
-                attrs.iter().fold(String::new(), |mut acc, attr| {
-                    acc.push_str(&format!("{attr:?} "));
-                    acc
-                })
-            )?,
-
-            ASTKind::FunctionDefinition(name, attrs, ast) => todo!(),
+            ASTKind::SizeOf(ty) => write!(f, "(SizeOf: {ty})")?,
+            ASTKind::Cast(ty, value, is_explicit) => {
+                let kind = if *is_explicit { "explicit" } else { "implicit" };
+                write!(f, "(Cast {kind}: {ty} {value})")?;
+            },
+            ASTKind::LabelDefinition(Some(name), attrs) => {
+                write!(f, "(LabelDefinition: {name} (")?;
+                attrs.iter().try_for_each(|attr| write!(f, "{attr:?} "))?;
+                write!(f, "))")?;
+            },
+            ASTKind::LabelDefinition(_, attrs) => {
+                write!(f, "(LabelDefinition: (")?;
+                attrs.iter().try_for_each(|attr| write!(f, "{attr:?} "))?;
+                write!(f, "))")?;
+            },
+
+            ASTKind::FunctionDefinition(name, generics, attrs, params, ret, body) => {
+                write!(f, "(FunctionDefinition: {name}")?;
+                if !generics.is_empty() {
+                    let generics =
+                        generics.iter().map(|param| param.name.as_str()).collect::<Vec<_>>();
+                    write!(f, "<{}>", generics.join(", "))?;
+                }
+                write!(f, " (")?;
+                attrs.iter().try_for_each(|attr| write!(f, "{attr:?} "))?;
+                let params = params.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, ") ({})", params.join(", "))?;
+                if let Some(ret) = ret {
+                    write!(f, " {ret}")?;
+                }
+                write!(f, " {body})")?;
+            },
+            ASTKind::VariableDefinition(name, ty, is_mutable, value) => {
+                let mutable = if *is_mutable { "'" } else { "" };
+                write!(f, "(VariableDefinition: {mutable}{name} {ty}")?;
+                if let Some(value) = value {
+                    write!(f, " {value}")?;
+                }
+                write!(f, ")")?;
+            },
+
+            ASTKind::Tag(name, args) => {
+                write!(f, "(Tag: {name} (")?;
+                args.iter().try_for_each(|arg| write!(f, "{arg} "))?;
+                write!(f, "))")?;
+            },
+            ASTKind::StructDefinition(name, fields) => {
+                write!(f, "(StructDefinition: {name} (")?;
+                fields.iter().try_for_each(|field| write!(f, "({} {}) ", field.name, field.ty))?;
+                write!(f, "))")?;
+            },
+
+            ASTKind::EnumDefinition(name, ty, variants) => {
+                write!(f, "(EnumDefinition: {name} {ty} (")?;
+                variants.iter().try_for_each(|variant| match &variant.value {
+                    Some(value) => write!(f, "({} {value}) ", variant.name),
+                    None => write!(f, "{} ", variant.name),
+                })?;
+                write!(f, "))")?;
+            },
+
+            ASTKind::TypeAlias(name, ty) => write!(f, "(TypeAlias: {name} {ty})")?,
+            ASTKind::ExternDeclaration(name, params, ret) => {
+                let params = params.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "(ExternDeclaration: {name} ({})", params.join(", "))?;
+                if let Some(ret) = ret {
+                    write!(f, " {ret}")?;
+                }
+                write!(f, ")")?;
+            },
+            ASTKind::Destructure(bindings, value) => {
+                write!(f, "(Destructure: {{")?;
+                for (i, binding) in bindings.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { ", " };
+                    let mutable = if binding.is_mutable { "'" } else { "" };
+                    write!(f, "{separator}{mutable}{}", binding.name)?;
+                    if let Some(ty) = &binding.ty {
+                        write!(f, " {ty}")?;
+                    }
+                }
+                write!(f, "}} {value})")?;
+            },
+            ASTKind::OperatorDefinition(_, op, params, ret, body) => {
+                let params = params.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "(OperatorDefinition: {op:?} ({}) {ret} {body})", params.join(", "))?;
+            },
 
             ASTKind::Return(Some(val)) => write!(f, "(Return: {val})")?,
             ASTKind::Return(_) => write!(f, "(Return)")?,
+            ASTKind::Loop(label, cond, body) => {
+                write!(f, "(Loop:")?;
+                if let Some(label) = label {
+                    write!(f, " {label}:")?;
+                }
+                if let Some(cond) = cond {
+                    write!(f, " {cond}")?;
+                }
+                write!(f, " {body})")?;
+            },
+            ASTKind::Break(Some(label)) => write!(f, "(Break: {label})")?,
+            ASTKind::Break(None) => write!(f, "(Break)")?,
+            ASTKind::Continue(Some(label)) => write!(f, "(Continue: {label})")?,
+            ASTKind::Continue(None) => write!(f, "(Continue)")?,
+            ASTKind::Match(value, arms, _) => {
+                write!(f, "(Match: {value}")?;
+                for arm in arms {
+                    let patterns = arm.patterns.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    write!(f, " ({} {})", patterns.join(", "), arm.body)?;
+                }
+                write!(f, ")")?;
+            },
+            ASTKind::Conditional(cond, then, otherwise) => {
+                write!(f, "(Conditional: {cond} {then}")?;
+                if let Some(otherwise) = otherwise {
+                    write!(f, " {otherwise}")?;
+                }
+                write!(f, ")")?;
+            },
 
             ASTKind::Interrupt(val) => write!(f, "(Interrupt: {val})")?,
-            ASTKind::Call(name, args, is_external) => write!(
-                f,
-                "(Call {} {name} Args: ({}))",
-                if *is_external { "external" } else { "internal" },
-                args.iter().fold(String::new(), |mut acc, arg| {
-                    acc.push_str(&format!("{arg} "));
-                    acc
-                })
-            )?,
-            ASTKind::Syscall(name, args) => write!(
-                f,
-                "(Syscall: {name} ({}))",
-                args.iter().fold(String::new(), |mut acc, arg| {
-                    acc.push_str(&format!("{arg} "));
-                    acc
-                })
-            )?,
+            ASTKind::Call(name, args, is_external) => {
+                let kind = if *is_external { "external" } else { "internal" };
+                write!(f, "(Call {kind} {name} Args: (")?;
+                args.iter().try_for_each(|arg| write!(f, "{arg} "))?;
+                write!(f, "))")?;
+            },
+            ASTKind::Syscall(name, args) => {
+                write!(f, "(Syscall: {name} (")?;
+                args.iter().try_for_each(|arg| write!(f, "{arg} "))?;
+                write!(f, "))")?;
+            },
         }
         Ok(())
     }
@@ -0,0 +1,283 @@
+use std::fmt::{Display, Formatter};
+
+use crate::ast::{ASTKind, LabelAttribute, Program, Type, AST};
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terminator {
+    // falls off the end of the function, or the program
+    Exit,
+    // runs straight into the next label
+    Fallthrough,
+    Return(bool),
+    Jump(usize),
+    // to the first block if the condition holds, otherwise the second
+    Branch(usize, usize),
+}
+
+pub struct BasicBlock<'ast> {
+    pub stmts:      Vec<&'ast AST>,
+    pub terminator: Terminator,
+}
+
+impl BasicBlock<'_> {
+    pub fn successors(&self) -> Vec<usize> {
+        match self.terminator {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch(then, otherwise) => vec![then, otherwise],
+            Terminator::Exit | Terminator::Fallthrough | Terminator::Return(_) => Vec::new(),
+        }
+    }
+}
+
+pub struct ControlFlowGraph<'ast> {
+    pub name:    Option<String>,
+    pub span:    Span,
+    pub blocks:  Vec<BasicBlock<'ast>>,
+    // what a function declares it returns, every path through it has to return a value then
+    pub returns: Option<&'ast Type>,
+
+    // the loops being lowered, innermost last, with where `continue` and `break` go
+    loops: Vec<(Option<&'ast str>, usize, usize)>,
+}
+
+impl<'ast> ControlFlowGraph<'ast> {
+    fn new(name: Option<String>, span: Span) -> Self {
+        Self { name, span, blocks: Vec::new(), returns: None, loops: Vec::new() }
+    }
+
+    // One graph per label or function, plus one for any code before the first label.
+    pub fn build_all(program: &'ast Program) -> Vec<Self> {
+        let mut graphs = Vec::new();
+        let mut region: (Option<String>, Span) = (None, Span::default());
+        let mut stmts: Vec<&'ast AST> = Vec::new();
+
+        for stmt in &program.stmts {
+            match &stmt.kind {
+                ASTKind::LabelDefinition(name, _) => {
+                    if region.0.is_some() || !stmts.is_empty() {
+                        graphs.push(Self::build(
+                            region,
+                            std::mem::take(&mut stmts),
+                            Terminator::Fallthrough,
+                        ));
+                    }
+                    region = (name.clone(), stmt.span);
+                },
+                ASTKind::FunctionDefinition(name, _, attrs, _, ret, body) => {
+                    let mut graph = Self::build(
+                        (Some(name.clone()), stmt.span.extend(&body.span)),
+                        std::iter::once(body.as_ref()),
+                        Terminator::Exit,
+                    );
+                    // a `noreturn` function never gets to the end anyway
+                    if !attrs.contains(&LabelAttribute::NoReturn) {
+                        graph.returns = ret.as_ref();
+                    }
+                    graphs.push(graph);
+                },
+                ASTKind::OperatorDefinition(name, _, _, ret, body) => {
+                    let mut graph = Self::build(
+                        (Some(name.clone()), stmt.span.extend(&body.span)),
+                        std::iter::once(body.as_ref()),
+                        Terminator::Exit,
+                    );
+                    graph.returns = Some(ret);
+                    graphs.push(graph);
+                },
+                // not code, just information about the program
+                ASTKind::Tag(..) => {},
+                _ => stmts.push(stmt),
+            }
+        }
+
+        if region.0.is_some() || !stmts.is_empty() {
+            graphs.push(Self::build(region, stmts, Terminator::Exit));
+        }
+
+        graphs
+    }
+
+    fn build(
+        (name, span): (Option<String>, Span), stmts: impl IntoIterator<Item = &'ast AST>,
+        end: Terminator,
+    ) -> Self {
+        let mut graph = Self::new(name, span);
+        let entry = graph.new_block();
+        let last = graph.lower(entry, stmts);
+        graph.blocks[last].terminator = end;
+        graph
+    }
+
+    fn new_block(&mut self) -> usize {
+        self.blocks.push(BasicBlock { stmts: Vec::new(), terminator: Terminator::Exit });
+        self.blocks.len() - 1
+    }
+
+    // Appends `stmts` to `current`, returning the block control ends up in.
+    fn lower(&mut self, mut current: usize, stmts: impl IntoIterator<Item = &'ast AST>) -> usize {
+        for stmt in stmts {
+            match &stmt.kind {
+                ASTKind::Block(inner) => current = self.lower(current, inner),
+                ASTKind::Return(value) => {
+                    self.blocks[current].stmts.push(stmt);
+                    if let Some(value) = value {
+                        current = self.lower_nested(current, value);
+                    }
+                    self.blocks[current].terminator = Terminator::Return(value.is_some());
+
+                    // anything after this has no predecessor
+                    current = self.new_block();
+                },
+                ASTKind::Loop(label, cond, body) => {
+                    let header = self.new_block();
+                    self.blocks[current].terminator = Terminator::Jump(header);
+                    self.blocks[header].stmts.push(stmt);
+
+                    let start = self.new_block();
+                    let exit = self.new_block();
+                    self.blocks[header].terminator = match cond {
+                        Some(_) => Terminator::Branch(start, exit),
+                        None => Terminator::Jump(start),
+                    };
+
+                    self.loops.push((label.as_deref(), header, exit));
+                    let end = self.lower(start, std::iter::once(body.as_ref()));
+                    self.loops.pop();
+
+                    self.blocks[end].terminator = Terminator::Jump(header);
+                    current = exit;
+                },
+                ASTKind::Break(label) | ASTKind::Continue(label) => {
+                    self.blocks[current].stmts.push(stmt);
+
+                    // the checker made sure the loop exists
+                    let Some(&(_, header, exit)) = self
+                        .loops
+                        .iter()
+                        .rev()
+                        .find(|(name, ..)| label.is_none() || *name == label.as_deref())
+                    else {
+                        continue;
+                    };
+
+                    let target = if matches!(stmt.kind, ASTKind::Break(_)) { exit } else { header };
+                    self.blocks[current].terminator = Terminator::Jump(target);
+                    current = self.new_block();
+                },
+                // the condition is evaluated at the end of the current block
+                ASTKind::Conditional(cond, then, otherwise) => {
+                    self.blocks[current].stmts.push(stmt);
+                    current = self.lower_nested(current, cond);
+
+                    let then_start = self.new_block();
+                    let then_end = self.lower(then_start, std::iter::once(then.as_ref()));
+                    let else_start = self.new_block();
+                    let else_end = self.lower(else_start, otherwise.as_deref());
+
+                    let join = self.new_block();
+                    self.blocks[current].terminator = Terminator::Branch(then_start, else_start);
+                    self.blocks[then_end].terminator = Terminator::Jump(join);
+                    self.blocks[else_end].terminator = Terminator::Jump(join);
+                    current = join;
+                },
+                // each arm is tested in turn, the last one can't fail once the match is exhaustive
+                ASTKind::Match(value, arms, is_exhaustive) => {
+                    self.blocks[current].stmts.push(stmt);
+                    current = self.lower_nested(current, value);
+
+                    let join = self.new_block();
+                    let mut test = current;
+                    for (i, arm) in arms.iter().enumerate() {
+                        let start = self.new_block();
+                        let end = self.lower(start, std::iter::once(&arm.body));
+                        self.blocks[end].terminator = Terminator::Jump(join);
+
+                        if *is_exhaustive && i == arms.len() - 1 {
+                            self.blocks[test].terminator = Terminator::Jump(start);
+                            test = join;
+                            continue;
+                        }
+
+                        let next = self.new_block();
+                        self.blocks[test].terminator = Terminator::Branch(start, next);
+                        test = next;
+                    }
+
+                    // nothing matched
+                    if test != join {
+                        self.blocks[test].terminator = Terminator::Jump(join);
+                    }
+                    current = join;
+                },
+                _ => {
+                    self.blocks[current].stmts.push(stmt);
+                    current = self.lower_nested(current, stmt);
+                },
+            }
+        }
+        current
+    }
+
+    // Control flow inside an expression, like `x <- c ? 1 : ret 0`, splits the block the
+    // statement around it is in
+    fn lower_nested(&mut self, current: usize, ast: &'ast AST) -> usize {
+        match &ast.kind {
+            ASTKind::Block(_)
+            | ASTKind::Return(_)
+            | ASTKind::Loop(..)
+            | ASTKind::Break(_)
+            | ASTKind::Continue(_)
+            | ASTKind::Conditional(..)
+            | ASTKind::Match(..) => self.lower(current, std::iter::once(ast)),
+            ASTKind::VariableDefinition(.., Some(value))
+            | ASTKind::Destructure(_, value)
+            | ASTKind::UnaryExpr(_, value)
+            | ASTKind::TypeAnnotation(_, value)
+            | ASTKind::Cast(_, value, _) => self.lower_nested(current, value),
+            ASTKind::BinaryExpr(_, lhs, rhs) => {
+                let current = self.lower_nested(current, lhs);
+                self.lower_nested(current, rhs)
+            },
+            ASTKind::Slice(value, lo, hi) => {
+                let current = self.lower_nested(current, value);
+                [lo, hi]
+                    .into_iter()
+                    .flatten()
+                    .fold(current, |current, bound| self.lower_nested(current, bound))
+            },
+            ASTKind::Call(callee, args, _) => {
+                let current = self.lower_nested(current, callee);
+                args.iter().fold(current, |current, arg| self.lower_nested(current, arg))
+            },
+            ASTKind::HeapLiteral(values) | ASTKind::Syscall(_, values) =>
+                values.iter().fold(current, |current, value| self.lower_nested(current, value)),
+            _ => current,
+        }
+    }
+
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![0];
+
+        while let Some(block) = stack.pop() {
+            if reachable[block] {
+                continue;
+            }
+            reachable[block] = true;
+            stack.extend(self.blocks[block].successors());
+        }
+
+        reachable
+    }
+}
+
+impl Display for ControlFlowGraph<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "(ControlFlowGraph: {}", self.name.as_deref().unwrap_or("<global>"))?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "  bb{i}: {} statements -> {:?}", block.stmts.len(), block.terminator)?;
+        }
+        write!(f, ")")
+    }
+}
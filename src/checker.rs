@@ -0,0 +1,2438 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{ASTKind, ArrayLength, DestructureBinding, EnumVariant, LabelAttribute, MatchArm,
+                 Operator, Parameter, Pattern, Program, StructField, Type, TypeParameter, AST,
+                 POINTER_SIZE, RETURN_VALUES};
+use crate::report::{LogHandler, Report, ReportKind, Result};
+use crate::scanner::Scanner;
+use crate::span::Span;
+
+// the elements of a struct or heap, only those of a struct have names
+type Fields = Vec<(Option<String>, Type)>;
+
+struct StructInfo {
+    span:   Span,
+    fields: Vec<(String, Type)>,
+    size:   Option<usize>,
+}
+
+struct EnumInfo {
+    span:     Span,
+    size:     usize,
+    // in the order they're defined
+    variants: Vec<Variant>,
+}
+
+#[derive(Clone)]
+struct Variant {
+    name:  String,
+    span:  Span,
+    value: i128,
+    size:  usize,
+    // the enum it belongs to
+    owner: String,
+}
+
+struct Extern {
+    span:   Span,
+    params: Vec<Parameter>,
+    ret:    Option<Type>,
+}
+
+struct Function {
+    span:       Span,
+    // names of its type parameters, if it's generic
+    generics:   Vec<String>,
+    params:     Vec<Parameter>,
+    ret:        Option<Type>,
+    // the types above are only resolved once the definition itself is checked
+    is_checked: bool,
+}
+
+struct Overload {
+    op:     Operator,
+    params: Vec<Type>,
+    ret:    Type,
+    label:  String,
+    span:   Span,
+}
+
+enum Alias {
+    // defined but not expanded yet, aliases may refer to ones defined later
+    Pending(Span, Type),
+    Expanding(Span),
+    // `None` if it's part of a cycle
+    Expanded(Span, Option<Type>),
+}
+
+impl Alias {
+    fn span(&self) -> Span {
+        match self {
+            Self::Pending(span, _) | Self::Expanding(span) | Self::Expanded(span, _) => *span,
+        }
+    }
+}
+
+struct Binding {
+    span:       Span,
+    ty:         Type,
+    is_mutable: bool,
+    // known for immutable bindings initialised with a constant expression
+    constant:   Option<i128>,
+}
+
+pub struct Checker {
+    filename:   &'static str,
+    handler:    LogHandler,
+    scopes:     Vec<HashMap<String, Binding>>,
+    structs:    HashMap<String, StructInfo>,
+    enums:      HashMap<String, EnumInfo>,
+    // the enums defining a variant of each name, it can only be used on its own where there's
+    // either just one or an enum it belongs to is expected
+    variants:   HashMap<String, Vec<String>>,
+    aliases:    HashMap<String, Alias>,
+    operators:  Vec<Overload>,
+    externs:    HashMap<String, Extern>,
+    functions:  HashMap<String, Function>,
+    labels:     HashSet<String>,
+    // names that weren't a binding or variant where they were used, they may still be symbols
+    // defined further on
+    unresolved: Vec<(String, Span)>,
+    // where the program starts, if anything is marked `entry`
+    entry:      Option<Span>,
+
+    // aliases currently being expanded, with what each one is written as
+    alias_chain:    Vec<(String, Span, String)>,
+    broken_aliases: HashSet<String>,
+
+    // type of whatever the current value is being stored into
+    expected:    Option<Type>,
+    // return type of the routine being checked, if it returns anything
+    returns:     Option<Type>,
+    is_noreturn: bool,
+    // names of the loops around whatever is being checked, innermost last
+    loops:       Vec<Option<String>>,
+    // type parameters of the function being checked, they stand for any type
+    type_params: Vec<String>,
+}
+
+impl Checker {
+    pub fn new(filename: &'static str, handler: LogHandler) -> Self {
+        Self {
+            filename,
+            handler,
+            scopes: vec![HashMap::new()],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            variants: HashMap::new(),
+            aliases: HashMap::new(),
+            operators: Vec::new(),
+            externs: HashMap::new(),
+            functions: HashMap::new(),
+            labels: HashSet::new(),
+            unresolved: Vec::new(),
+            entry: None,
+            alias_chain: Vec::new(),
+            broken_aliases: HashSet::new(),
+            expected: None,
+            returns: None,
+            is_noreturn: false,
+            loops: Vec::new(),
+            type_params: Vec::new(),
+        }
+    }
+
+    fn report(&self, report: Report) {
+        let (priority, log) = report.into();
+        self.handler.add_log(priority, log);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    pub fn check(&mut self, program: &mut Program) {
+        // top level aliases and functions can be used before they're defined
+        for stmt in &program.stmts {
+            match &stmt.kind {
+                ASTKind::TypeAlias(name, ty) => self.define_alias(name, ty, stmt.span),
+                ASTKind::FunctionDefinition(name, generics, _, params, ret, _) => {
+                    self.define_function(name, generics, params, ret.as_ref(), stmt.span);
+                },
+                _ => {},
+            }
+        }
+
+        program.stmts.iter_mut().for_each(|stmt| {
+            self.check_node(stmt);
+        });
+
+        for (name, span) in std::mem::take(&mut self.unresolved) {
+            if self.labels.contains(&name)
+                || self.functions.contains_key(&name)
+                || self.externs.contains_key(&name)
+            {
+                continue;
+            }
+
+            self.report(
+                ReportKind::UndefinedVariable
+                    .title(format!("Undefined binding '{name}'"))
+                    .span(span)
+                    .label("not a binding, variant, function or label"),
+            );
+        }
+    }
+
+    // Returns the type of the node, if it has a known one.
+    // Integer literals are left untyped so they fit whatever they're used with.
+    fn check_node(&mut self, ast: &mut AST) -> Option<Type> {
+        match &mut ast.kind {
+            ASTKind::VariableDefinition(name, ty, is_mutable, value) => {
+                self.resolve_type(ty, ast.span);
+
+                let mut constant = None;
+                if let Some(value) = value {
+                    self.check_value(value, Some(ty));
+                    if !*is_mutable {
+                        constant = self.eval_const(value).ok();
+                    }
+                }
+
+                self.scopes.last_mut().expect("Checker has no scope").insert(
+                    name.clone(),
+                    Binding { span: ast.span, ty: ty.clone(), is_mutable: *is_mutable, constant },
+                );
+                None
+            },
+
+            ASTKind::ExternDeclaration(name, params, ret) => {
+                self.check_extern(name, params, ret, ast.span);
+                None
+            },
+
+            ASTKind::Destructure(bindings, value) => {
+                self.check_destructure(bindings, value, ast.span);
+                None
+            },
+
+            ASTKind::StructDefinition(name, fields) => {
+                self.check_struct(name, fields, ast.span);
+                None
+            },
+
+            ASTKind::EnumDefinition(name, ty, variants) => {
+                self.check_enum(name, ty, variants, ast.span);
+                None
+            },
+
+            ASTKind::TypeAlias(name, ty) => {
+                if !matches!(self.aliases.get(name), Some(alias) if alias.span() == ast.span) {
+                    self.define_alias(name, ty, ast.span);
+                }
+
+                self.expand_alias(name);
+                if let Some(Alias::Expanded(_, Some(expanded))) = self.aliases.get(name) {
+                    *ty = expanded.clone();
+                }
+                None
+            },
+
+            ASTKind::OperatorDefinition(label, op, params, ret, body) => {
+                self.check_operator(label, op, params, ret, ast.span);
+                self.check_routine(params, Some(ret.clone()), body);
+                None
+            },
+            ASTKind::LabelDefinition(label, attrs) => {
+                self.check_attributes(attrs, None, ast.span);
+                self.labels.extend(label.clone());
+                None
+            },
+            ASTKind::FunctionDefinition(name, generics, attrs, params, ret, body) => {
+                self.check_attributes(attrs, Some((params, ret.as_ref())), ast.span);
+                match self.functions.get(name.as_str()) {
+                    Some(function) if function.span == ast.span => {},
+                    _ => self.define_function(name, generics, params, ret.as_ref(), ast.span),
+                }
+
+                self.check_generics(generics, attrs, params, ret.as_ref(), ast.span);
+                let outer_params = std::mem::replace(
+                    &mut self.type_params,
+                    generics.iter().map(|param| param.name.clone()).collect(),
+                );
+                for param in params.iter_mut() {
+                    self.resolve_type(&mut param.ty, param.span);
+                }
+                if let Some(ret) = ret {
+                    self.resolve_type(ret, ast.span);
+                    self.check_return_type(ret, ast.span);
+                }
+
+                // a duplicate doesn't get to replace the signature of the first one
+                if let Some(function) = self.functions.get_mut(name.as_str()) {
+                    if function.span == ast.span {
+                        function.params.clone_from(params);
+                        function.ret.clone_from(ret);
+                        function.is_checked = true;
+                    }
+                }
+
+                let is_noreturn = attrs.contains(&LabelAttribute::NoReturn);
+                let outer = std::mem::replace(&mut self.is_noreturn, is_noreturn);
+                self.check_routine(params, ret.clone(), body);
+                self.is_noreturn = outer;
+                self.type_params = outer_params;
+                None
+            },
+
+            ASTKind::Identifier(name) => {
+                if let Some(binding) = self.lookup(name) {
+                    return Some(binding.ty.clone());
+                }
+
+                // variants are replaced by their value, codegen never sees them
+                let Some(Variant { value, size, owner, .. }) = self.variant(name).cloned()
+                else {
+                    if !self.check_ambiguous(name, ast.span) {
+                        self.unresolved.push((name.clone(), ast.span));
+                    }
+                    return None;
+                };
+                let literal = ASTKind::IntegerLiteral(value.unsigned_abs() as usize);
+                let literal = match value < 0 {
+                    true =>
+                        ASTKind::UnaryExpr(Operator::Negative, Box::new(literal.into_ast(ast.span))),
+                    false => literal,
+                };
+                let ty = Type::Alias(owner, Box::new(Type::Size(size)));
+                ast.kind =
+                    ASTKind::TypeAnnotation(ty.clone(), Box::new(literal.into_ast(ast.span)));
+                Some(ty)
+            },
+            ASTKind::CharLiteral(_) => Some(Type::Size(1)),
+            ASTKind::StringLiteral(_) =>
+                Some(Type::Heap { is_pointer: true, contents: vec![Type::Size(1)] }),
+
+            // `a; b` runs `a` for its effects only, the whole thing is worth `b`
+            ASTKind::BinaryExpr(Operator::Sequence, lhs, rhs) => {
+                self.check_node(lhs);
+                self.check_node(rhs)
+            },
+
+            ASTKind::BinaryExpr(Operator::Assign, target, value) => {
+                let ty = self.check_mutation(target, ast.span);
+                self.check_value(value, ty.as_ref());
+                ty
+            },
+            ASTKind::UnaryExpr(
+                Operator::MutatePre
+                | Operator::MutatePost
+                | Operator::Increment
+                | Operator::Decrement,
+                target,
+            ) => self.check_mutation(target, ast.span),
+
+            ASTKind::BinaryExpr(
+                op @ (Operator::Eq
+                | Operator::Neq
+                | Operator::Lt
+                | Operator::Le
+                | Operator::Gt
+                | Operator::Ge),
+                lhs,
+                rhs,
+            ) => {
+                // `x = A` looks for `A` among the variants of whatever `x` is
+                let lhs_ty = self.check_node(lhs);
+                let operands = [lhs_ty.clone(), self.check_value(rhs, lhs_ty.as_ref())];
+                if let Some(overload) = self.find_overload(op, &operands, ast.span) {
+                    return Some(lower_operator(ast, &operands, overload));
+                }
+                Some(Type::Size(1))
+            },
+            ASTKind::BinaryExpr(Operator::Index, base, index) =>
+                self.check_index(base, index).map(|(element, _)| element),
+            ASTKind::BinaryExpr(Operator::Access, ..) => self.check_access(ast),
+            ASTKind::Slice(base, lo, hi) => {
+                let (lowered, ty) =
+                    self.check_slice(base, lo.as_deref(), hi.as_deref(), ast.span)?;
+                if let Some(lowered) = lowered {
+                    ast.kind = lowered;
+                }
+                Some(ty)
+            },
+            ASTKind::BinaryExpr(op, lhs, rhs) => {
+                let lhs_ty = self.check_node(lhs);
+                let rhs_ty = self.check_node(rhs);
+
+                let operands = [lhs_ty, rhs_ty];
+                if let Some(overload) = self.find_overload(op, &operands, ast.span) {
+                    return Some(lower_operator(ast, &operands, overload));
+                }
+                let [lhs_ty, rhs_ty] = operands;
+
+                // untyped literals take on the size of wherever they end up
+                let size = [&lhs_ty, &rhs_ty]
+                    .into_iter()
+                    .filter_map(|ty| ty.as_ref().and_then(Type::size))
+                    .max()
+                    .or_else(|| self.expected.as_ref().and_then(Type::size));
+                self.check_arithmetic(op, lhs, rhs, size.unwrap_or(POINTER_SIZE));
+                lhs_ty.or(rhs_ty)
+            },
+
+            ASTKind::UnaryExpr(Operator::Deref, inner) =>
+                self.check_node(inner).and_then(|ty| ty.pointee()),
+            ASTKind::UnaryExpr(op @ (Operator::Negative | Operator::Not), operand) => {
+                let operands = [self.check_node(operand)];
+                if let Some(overload) = self.find_overload(op, &operands, ast.span) {
+                    return Some(lower_operator(ast, &operands, overload));
+                }
+                let [ty] = operands;
+                ty
+            },
+            ASTKind::UnaryExpr(_, operand) => self.check_node(operand),
+            ASTKind::TypeAnnotation(ty, operand) => {
+                self.resolve_type(ty, ast.span);
+                match &mut operand.kind {
+                    // `!f<T>` is also what a generic function's type parameters are inferred from
+                    ASTKind::Call(callee, args, false) if self.is_generic(callee) => {
+                        self.check_generic_call(callee, args, Some(ty), operand.span);
+                    },
+                    _ => {
+                        self.check_node(operand);
+                    },
+                }
+                Some(ty.clone())
+            },
+            ASTKind::SizeOf(ty) => {
+                self.resolve_type(ty, ast.span);
+                None
+            },
+            ASTKind::Cast(ty, value, is_explicit) => {
+                self.resolve_type(ty, ast.span);
+                let from = self.check_node(value);
+                self.check_cast(value, from.as_ref(), ty, ast.span, *is_explicit);
+                Some(ty.clone())
+            },
+
+            ASTKind::Return(value) => {
+                if self.is_noreturn {
+                    self.report(
+                        ReportKind::ReturnFromNoReturn
+                            .title("Returning from a `noreturn` function")
+                            .span(ast.span)
+                            .note("HINT: Exit the program instead, or drop the attribute"),
+                    );
+                }
+
+                match (value, self.returns.clone()) {
+                    (Some(value), Some(ret))
+                        if ret.return_values().is_some()
+                            && matches!(value.kind, ASTKind::HeapLiteral(_)) =>
+                        self.check_return_values(value, &ret),
+                    (Some(value), Some(ret)) => {
+                        let from = self.check_value(value, Some(&ret));
+                        let span = value.span;
+                        self.check_cast(value, from.as_ref(), &ret, span, false);
+                    },
+                    (Some(value), None) => {
+                        self.check_node(value);
+                    },
+                    (None, Some(ret)) => self.report(
+                        ReportKind::InvalidReturn
+                            .title("Returning nothing from a function that returns a value")
+                            .span(ast.span)
+                            .label(format!("expected a `{ret:#}` to return"))
+                            .note("HINT: Give `ret` a value"),
+                    ),
+                    (None, None) => {},
+                }
+                None
+            },
+
+            ASTKind::Conditional(cond, then, otherwise) => {
+                self.check_condition(cond);
+                let then = self.check_node(then);
+                let otherwise = otherwise.as_mut().and_then(|otherwise| self.check_node(otherwise));
+                then.filter(|then| otherwise.as_ref() == Some(then))
+            },
+
+            ASTKind::Match(value, arms, is_exhaustive) =>
+                self.check_match(value, arms, is_exhaustive),
+
+            ASTKind::Loop(label, cond, body) => {
+                if let Some(cond) = cond {
+                    self.check_condition(cond);
+                }
+
+                self.loops.push(label.clone());
+                self.check_node(body);
+                self.loops.pop();
+                None
+            },
+            ASTKind::Break(label) => {
+                self.check_loop_control("break", label.as_deref(), ast.span);
+                None
+            },
+            ASTKind::Continue(label) => {
+                self.check_loop_control("continue", label.as_deref(), ast.span);
+                None
+            },
+
+            ASTKind::Block(stmts) => {
+                self.scopes.push(HashMap::new());
+                stmts.iter_mut().for_each(|stmt| {
+                    self.check_node(stmt);
+                });
+                self.scopes.pop();
+                None
+            },
+
+            ASTKind::HeapLiteral(values) | ASTKind::Syscall(_, values) => {
+                values.iter_mut().for_each(|value| {
+                    self.check_node(value);
+                });
+                None
+            },
+            ASTKind::Call(callee, args, true) => match &callee.kind {
+                ASTKind::Identifier(name) => self.check_external_call(name, args, ast.span),
+                _ => {
+                    self.check_node(callee);
+                    args.iter_mut().for_each(|arg| {
+                        self.check_node(arg);
+                    });
+                    None
+                },
+            },
+            ASTKind::Call(callee, args, false) if self.is_generic(callee) => {
+                let ret = self.check_generic_call(callee, args, None, ast.span);
+                annotate_values(ast, ret.as_ref());
+                ret
+            },
+            ASTKind::Call(callee, args, false) => match &callee.kind {
+                ASTKind::Identifier(name) if self.functions.contains_key(name) => {
+                    let function = &self.functions[name];
+                    let (declared, params) = (function.span, function.params.clone());
+                    let (ret, is_checked) = (function.ret.clone(), function.is_checked);
+
+                    self.check_arguments(name, &params, declared, args, ast.span, is_checked);
+                    let ret = ret.filter(|_| is_checked);
+                    annotate_values(ast, ret.as_ref());
+                    ret
+                },
+                _ => {
+                    self.check_node(callee);
+                    args.iter_mut().for_each(|arg| {
+                        self.check_node(arg);
+                    });
+                    None
+                },
+            },
+
+            _ => None,
+        }
+    }
+
+    fn check_struct(&mut self, name: &str, fields: &mut [StructField], span: Span) {
+        if !self.define_type(name, span) {
+            return;
+        }
+
+        // registered up front so fields may point back at it
+        self.structs.insert(name.to_string(), StructInfo { span, fields: Vec::new(), size: None });
+
+        let mut seen: HashMap<&str, Span> = HashMap::new();
+        let mut size = Some(0);
+        for field in fields.iter_mut() {
+            if let Some(first) = seen.insert(&field.name, field.span) {
+                self.report(
+                    ReportKind::DuplicateField
+                        .title(format!("Field '{}' is already declared in '{name}'", field.name))
+                        .span(field.span)
+                        .label("declared again here")
+                        .info(format!("first declared at {first}")),
+                );
+            }
+
+            self.resolve_type(&mut field.ty, field.span);
+            let label = match field.ty.expanded() {
+                Type::Struct(inner, _) if inner == name => "a struct can't contain itself",
+                Type::Array { elems: None, .. } => "arrays in structs need a length",
+                ty => {
+                    size = size.zip(ty.size()).map(|(size, field)| size + field);
+                    continue;
+                },
+            };
+
+            size = None;
+            self.report(
+                ReportKind::UnsizedField
+                    .title(format!("Field '{}' has no fixed size", field.name))
+                    .span(field.span)
+                    .label(label)
+                    .note(format!("HINT: Store a pointer instead, e.g. [{}]", field.ty)),
+            );
+        }
+
+        let info = self.structs.get_mut(name).expect("struct was just registered");
+        info.fields = fields.iter().map(|field| (field.name.clone(), field.ty.clone())).collect();
+        info.size = size;
+    }
+
+    fn check_enum(&mut self, name: &str, ty: &mut Type, variants: &mut [EnumVariant], span: Span) {
+        if !self.define_type(name, span) {
+            return;
+        }
+
+        self.resolve_type(ty, span);
+        let size = match ty.expanded() {
+            Type::Size(size) if (1..=POINTER_SIZE).contains(size) => *size,
+            _ => {
+                self.report(
+                    ReportKind::InvalidEnumType
+                        .title(format!("Enum '{name}' can't be backed by `{ty:#}`"))
+                        .span(span)
+                        .note(format!("HINT: Use a size of at most {POINTER_SIZE} bytes, e.g. `enum {name} 4`")),
+                );
+                POINTER_SIZE
+            },
+        };
+        self.enums.insert(name.to_string(), EnumInfo { span, size, variants: Vec::new() });
+
+        // discriminants see this enum's variants before any other's
+        let outer =
+            self.expected.replace(Type::Alias(name.to_string(), Box::new(Type::Size(size))));
+        let mut seen: HashMap<i128, (&str, Span)> = HashMap::new();
+        let mut next = Some(0);
+        for variant in variants.iter_mut() {
+            let value = match &mut variant.value {
+                Some(value) => {
+                    self.check_node(value);
+                    match self.eval_const(value) {
+                        Ok(val) => Some(val),
+                        Err(report) => {
+                            self.report(*report);
+                            None
+                        },
+                    }
+                },
+                None => next,
+            };
+
+            let first = self.enums[name].variants.iter().find(|first| first.name == variant.name);
+            if let Some(first) = first {
+                self.report(
+                    ReportKind::DuplicateVariant
+                        .title(format!("Variant '{}' is already defined", variant.name))
+                        .span(variant.span)
+                        .label("defined again here")
+                        .info(format!("first defined at {}", first.span)),
+                );
+                next = value.map(|value| value + 1);
+                continue;
+            }
+
+            // leave out anything broken rather than cascading errors from it
+            let Some(value) = value
+            else {
+                next = None;
+                continue;
+            };
+            next = Some(value + 1);
+
+            if !fits(value, size) {
+                self.report(
+                    ReportKind::InvalidDiscriminant
+                        .title(format!("Discriminant {value} doesn't fit in {size} bytes"))
+                        .span(variant.span)
+                        .label(match variant.value {
+                            Some(_) => "this value",
+                            None => "counted on from the variant before",
+                        })
+                        .note(format!("HINT: Give '{name}' a larger size")),
+                );
+            }
+
+            if let Some((other, first)) = seen.insert(value, (&variant.name, variant.span)) {
+                self.report(
+                    ReportKind::DuplicateDiscriminant
+                        .title(format!(
+                            "Discriminant {value} is used by both '{other}' and '{}'",
+                            variant.name
+                        ))
+                        .span(variant.span)
+                        .label(format!("also {value}"))
+                        .info(format!("'{other}' is defined at {first}")),
+                );
+            }
+
+            self.variants.entry(variant.name.clone()).or_default().push(name.to_string());
+            self.enums.get_mut(name).expect("enum was just registered").variants.push(Variant {
+                name: variant.name.clone(),
+                span: variant.span,
+                value,
+                size,
+                owner: name.to_string(),
+            });
+        }
+        self.expected = outer;
+    }
+
+    // Structs, enums and aliases share a namespace, returns false if `name` is already taken.
+    fn define_type(&self, name: &str, span: Span) -> bool {
+        let first = match (self.structs.get(name), self.enums.get(name), self.aliases.get(name)) {
+            (Some(StructInfo { span, .. }), ..) | (_, Some(EnumInfo { span, .. }), _) => *span,
+            (.., Some(alias)) => alias.span(),
+            (None, None, None) => return true,
+        };
+
+        self.report(
+            ReportKind::DuplicateType
+                .title(format!("Type '{name}' is already defined"))
+                .span(span)
+                .label("redefined here")
+                .info(format!("first defined at {first}")),
+        );
+        false
+    }
+
+    fn define_alias(&mut self, name: &str, ty: &Type, span: Span) {
+        if self.define_type(name, span) {
+            self.aliases.insert(name.to_string(), Alias::Pending(span, ty.clone()));
+        }
+    }
+
+    // Expands an alias once, every later use takes the result from `self.aliases`.
+    fn expand_alias(&mut self, name: &str) {
+        match self.aliases.get(name) {
+            Some(Alias::Pending(..)) => {},
+            Some(Alias::Expanding(_)) => return self.report_alias_cycle(name),
+            Some(Alias::Expanded(..)) | None => return,
+        }
+
+        let Some(Alias::Pending(span, mut ty)) = self.aliases.remove(name)
+        else {
+            unreachable!("alias was just pending");
+        };
+        self.aliases.insert(name.to_string(), Alias::Expanding(span));
+
+        self.alias_chain.push((name.to_string(), span, ty.to_string()));
+        self.resolve_type(&mut ty, span);
+        self.alias_chain.pop();
+
+        let expanded = (!self.broken_aliases.contains(name)).then_some(ty);
+        self.aliases.insert(name.to_string(), Alias::Expanded(span, expanded));
+    }
+
+    fn report_alias_cycle(&mut self, name: &str) {
+        let start = self
+            .alias_chain
+            .iter()
+            .position(|(alias, ..)| alias == name)
+            .expect("expanding alias isn't in the chain");
+        let cycle = &self.alias_chain[start..];
+
+        let names = cycle.iter().map(|(alias, ..)| alias.as_str()).chain([name]);
+        let mut report = ReportKind::AliasCycle
+            .title(format!("Type alias '{name}' refers back to itself"))
+            .span(cycle[0].1)
+            .label(names.collect::<Vec<_>>().join(" -> "));
+        for (alias, span, ty) in cycle {
+            report = report.info(format!("'{alias}' is defined as `{ty}` at {span}"));
+        }
+        self.report(report.note("HINT: Use a struct if the type needs to refer to itself"));
+
+        let cycle = cycle.iter().map(|(alias, ..)| alias.clone()).collect::<Vec<_>>();
+        self.broken_aliases.extend(cycle);
+    }
+
+    fn check_extern(
+        &mut self, name: &str, params: &mut [Parameter], ret: &mut Option<Type>, span: Span,
+    ) {
+        for param in params.iter_mut() {
+            self.resolve_type(&mut param.ty, param.span);
+        }
+        if let Some(ret) = ret {
+            self.resolve_type(ret, span);
+        }
+
+        if let Some(first) = self.externs.get(name) {
+            return self.report(
+                ReportKind::DuplicateExtern
+                    .title(format!("External symbol '{name}' is already declared"))
+                    .span(span)
+                    .label("declared again here")
+                    .info(format!("first declared at {}", first.span)),
+            );
+        }
+
+        self.externs.insert(name.to_string(), Extern {
+            span,
+            params: params.to_vec(),
+            ret: ret.clone(),
+        });
+    }
+
+    // `@name(...)` has to match its `extern` declaration, arguments convert as if by `->`.
+    fn check_external_call(&mut self, name: &str, args: &mut [AST], span: Span) -> Option<Type> {
+        let Some(Extern { span: declared, params, ret }) = self.externs.get(name)
+        else {
+            args.iter_mut().for_each(|arg| {
+                self.check_node(arg);
+            });
+            self.report(
+                ReportKind::UndeclaredExtern
+                    .title(format!("External symbol '{name}' is never declared"))
+                    .span(span)
+                    .note(format!("HINT: Declare its signature, e.g. `extern {name}(a 8) 8`")),
+            );
+            return None;
+        };
+        let (declared, params, ret) = (*declared, params.clone(), ret.clone());
+
+        self.check_arguments(name, &params, declared, args, span, true);
+        ret
+    }
+
+    // Arguments are converted to their parameter's type as if by `->`.
+    // Only the count is checked while the parameter types are still unresolved.
+    fn check_arguments(
+        &mut self, name: &str, params: &[Parameter], declared: Span, args: &mut [AST], span: Span,
+        is_resolved: bool,
+    ) {
+        self.check_argument_count(name, params, declared, args, span);
+
+        for (i, arg) in args.iter_mut().enumerate() {
+            let Some(param) = params.get(i).filter(|_| is_resolved)
+            else {
+                self.check_node(arg);
+                continue;
+            };
+
+            let from = self.check_value(arg, Some(&param.ty));
+            self.pass_argument(arg, from.as_ref(), &param.ty);
+        }
+    }
+
+    fn check_argument_count(
+        &self, name: &str, params: &[Parameter], declared: Span, args: &[AST], span: Span,
+    ) {
+        if args.len() == params.len() {
+            return;
+        }
+
+        let plural = if params.len() == 1 { "" } else { "s" };
+        self.report(
+            ReportKind::ArgumentCount
+                .title(format!(
+                    "'{name}' takes {} argument{plural}, but {} {} given",
+                    params.len(),
+                    args.len(),
+                    if args.len() == 1 { "was" } else { "were" }
+                ))
+                .span(span)
+                .info(format!("declared at {declared}")),
+        );
+    }
+
+    fn pass_argument(&mut self, arg: &mut AST, from: Option<&Type>, to: &Type) {
+        let span = arg.span;
+        self.check_cast(arg, from, to, span, false);
+
+        // aggregates are passed by pointer, see `Parameter::local_type`
+        if !to.fits_register() && !self.is_generic_type(to) {
+            let inner = std::mem::replace(&mut arg.kind, ASTKind::Block(Vec::new()));
+            arg.kind = ASTKind::UnaryExpr(Operator::AddressOf, Box::new(inner.into_ast(span)));
+        }
+    }
+
+    fn is_generic(&self, callee: &AST) -> bool {
+        matches!(&callee.kind, ASTKind::Identifier(name)
+            if self.lookup(name).is_none()
+                && self.functions.get(name).is_some_and(|function| !function.generics.is_empty()))
+    }
+
+    // Infers what the type parameters stand for from the arguments, and from `annotation`, the
+    // type `!f<T>` says the call yields. The callee becomes the instance it calls.
+    fn check_generic_call(
+        &mut self, callee: &mut AST, args: &mut [AST], annotation: Option<&Type>, span: Span,
+    ) -> Option<Type> {
+        let ASTKind::Identifier(name) = &callee.kind
+        else {
+            return None;
+        };
+        let name = name.clone();
+        let function = &self.functions[&name];
+        let (declared, generics, params) =
+            (function.span, function.generics.clone(), function.params.clone());
+        let (ret, is_checked) = (function.ret.clone(), function.is_checked);
+
+        if !is_checked {
+            args.iter_mut().for_each(|arg| {
+                self.check_node(arg);
+            });
+            self.report(
+                ReportKind::UninferredGeneric
+                    .title(format!("Generic function '{name}' is called before it's defined"))
+                    .span(span)
+                    .info(format!("defined at {declared}"))
+                    .note("HINT: Its signature is needed to infer the types, move the definition above"),
+            );
+            return None;
+        }
+        self.check_argument_count(&name, &params, declared, args, span);
+
+        let mut found = vec![None; generics.len()];
+        if let (Some(ret), Some(annotation)) = (&ret, annotation) {
+            infer(ret, annotation, &generics, &mut found);
+        }
+        let mut from = Vec::new();
+        for (i, arg) in args.iter_mut().enumerate() {
+            let ty = self.check_value(arg, params.get(i).map(|param| &param.ty));
+            if let Some((param, ty)) = params.get(i).zip(ty.as_ref()) {
+                infer(&param.ty, ty, &generics, &mut found);
+            }
+            from.push(ty);
+        }
+        // whatever the call is stored into only settles what the arguments leave open
+        if let (Some(ret), Some(expected)) = (&ret, self.expected.clone()) {
+            infer(ret, &expected, &generics, &mut found);
+        }
+
+        let missing = generics
+            .iter()
+            .zip(&found)
+            .filter(|(_, ty)| ty.is_none())
+            .map(|(param, _)| format!("'{param}'"))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            self.report(
+                ReportKind::UninferredGeneric
+                    .title(format!("Cannot infer {} for this call to '{name}'", missing.join(", ")))
+                    .span(span)
+                    .info(format!("defined at {declared}"))
+                    .note(format!("HINT: Give the arguments a type, or annotate what it yields e.g. `!{name}<8>`")),
+            );
+            return None;
+        }
+
+        let types = found.into_iter().flatten().collect::<Vec<_>>();
+        let unfit = generics
+            .iter()
+            .zip(&types)
+            .find(|(_, ty)| !ty.fits_register() && !self.is_generic_type(ty));
+        if let Some((param, ty)) = unfit {
+            self.report(
+                ReportKind::InvalidGeneric
+                    .title(format!("'{param}' can't stand for `{ty:#}` in this call to '{name}'"))
+                    .span(span)
+                    .label("doesn't fit in a register")
+                    .info(format!("defined at {declared}"))
+                    .note("HINT: Type parameters stand for scalars, pass a pointer instead"),
+            );
+            return None;
+        }
+        let substitution = generics.into_iter().zip(types.iter().cloned()).collect::<Vec<_>>();
+        for ((arg, from), param) in args.iter_mut().zip(&from).zip(&params) {
+            self.pass_argument(arg, from.as_ref(), &param.ty.substitute(&substitution));
+        }
+
+        callee.kind = ASTKind::Instance(name, types);
+        ret.map(|ret| ret.substitute(&substitution))
+    }
+
+    fn define_function(
+        &mut self, name: &str, generics: &[TypeParameter], params: &[Parameter],
+        ret: Option<&Type>, span: Span,
+    ) {
+        if let Some(first) = self.functions.get(name) {
+            return self.report(
+                ReportKind::DuplicateFunction
+                    .title(format!("Function '{name}' is already defined"))
+                    .span(span)
+                    .label("defined again here")
+                    .info(format!("first defined at {}", first.span)),
+            );
+        }
+
+        self.functions.insert(name.to_string(), Function {
+            span,
+            generics: generics.iter().map(|param| param.name.clone()).collect(),
+            params: params.to_vec(),
+            ret: ret.cloned(),
+            is_checked: false,
+        });
+    }
+
+    // Every type parameter has to be inferable from a call, so it has to be part of the signature
+    fn check_generics(
+        &self, generics: &[TypeParameter], attrs: &[LabelAttribute], params: &[Parameter],
+        ret: Option<&Type>, span: Span,
+    ) {
+        let mut seen: HashMap<&str, Span> = HashMap::new();
+        for param in generics {
+            if let Some(first) = seen.insert(&param.name, param.span) {
+                self.report(
+                    ReportKind::InvalidGeneric
+                        .title(format!("Type parameter '{}' is declared twice", param.name))
+                        .span(param.span)
+                        .info(format!("first declared at {first}")),
+                );
+                continue;
+            }
+
+            let is_used =
+                params.iter().map(|param| &param.ty).chain(ret).any(|ty| mentions(ty, &param.name));
+            if !is_used {
+                self.report(
+                    ReportKind::InvalidGeneric
+                        .title(format!("Type parameter '{}' can never be inferred", param.name))
+                        .span(param.span)
+                        .label("none of the parameters or the return type use it"),
+                );
+            }
+        }
+
+        // only instances exist, under names of their own
+        let attr = attrs
+            .iter()
+            .find(|attr| matches!(attr, LabelAttribute::Entry | LabelAttribute::Export));
+        if let Some(attr) = attr.filter(|_| !generics.is_empty()) {
+            self.report(
+                ReportKind::InvalidGeneric
+                    .title(format!("A generic function can't be `{}`", attr.name()))
+                    .span(span)
+                    .note("HINT: Wrap a call to it in a function that isn't generic"),
+            );
+        }
+    }
+
+    // Whether `ty` is or contains a type parameter of the function being checked
+    fn is_generic_type(&self, ty: &Type) -> bool {
+        self.type_params.iter().any(|param| mentions(ty, param))
+    }
+
+    // `function` is the signature if the attributes are on a function rather than a label.
+    fn check_attributes(
+        &mut self, attrs: &[LabelAttribute], function: Option<(&[Parameter], Option<&Type>)>,
+        span: Span,
+    ) {
+        if attrs.contains(&LabelAttribute::Entry) {
+            match self.entry {
+                Some(first) => self.report(
+                    ReportKind::DuplicateEntry
+                        .title("The program has more than one entry point")
+                        .span(span)
+                        .label("marked `entry` again here")
+                        .info(format!("first entry point at {first}")),
+                ),
+                None => self.entry = Some(span),
+            }
+        }
+
+        for attr in attrs {
+            let problem = match (attr, function) {
+                (LabelAttribute::Inline | LabelAttribute::NoReturn, None) =>
+                    Some(format!("only functions can be `{}`", attr.name())),
+                (LabelAttribute::NoReturn, Some((_, Some(ret)))) =>
+                    Some(format!("a `noreturn` function can't return `{ret}`")),
+                (LabelAttribute::Entry, Some((params, _))) if !params.is_empty() =>
+                    Some(String::from("the entry point is never given any arguments")),
+                _ => None,
+            };
+
+            if let Some(problem) = problem {
+                self.report(
+                    ReportKind::InvalidAttribute
+                        .title(format!("Invalid attribute `{}`", attr.name()))
+                        .span(span)
+                        .label(problem),
+                );
+            }
+        }
+    }
+
+    // Checks the body of a function or operator with its parameters in scope.
+    fn check_routine(&mut self, params: &[Parameter], ret: Option<Type>, body: &mut AST) {
+        self.scopes.push(HashMap::new());
+        for param in params {
+            // whether a type parameter is passed by reference isn't known yet
+            let ty = match self.is_generic_type(&param.ty) {
+                true => param.ty.clone(),
+                false => param.local_type(),
+            };
+            self.scopes.last_mut().expect("Checker has no scope").insert(
+                param.name.clone(),
+                Binding { span: param.span, ty, is_mutable: false, constant: None },
+            );
+        }
+
+        // a loop around the definition can't be left from inside it
+        let loops = std::mem::take(&mut self.loops);
+        let outer = std::mem::replace(&mut self.returns, ret);
+        self.check_node(body);
+        self.returns = outer;
+        self.loops = loops;
+        self.scopes.pop();
+    }
+
+    // A heap returned by value is handed back in registers, so there can only be so many
+    // elements and each has to fit in one
+    fn check_return_type(&self, ret: &Type, span: Span) {
+        let Some(values) = ret.return_values()
+        else {
+            return;
+        };
+
+        if values.len() > RETURN_VALUES {
+            return self.report(
+                ReportKind::InvalidReturn
+                    .title(format!("Cannot return {} values at once", values.len()))
+                    .span(span)
+                    .label(format!("`{ret:#}` is returned by value"))
+                    .note(format!(
+                        "HINT: At most {RETURN_VALUES} are returned in registers, return a pointer to the rest"
+                    )),
+            );
+        }
+        if let Some(value) =
+            values.iter().find(|value| !value.fits_register() && !self.is_generic_type(value))
+        {
+            self.report(
+                ReportKind::InvalidReturn
+                    .title(format!("Cannot return `{ret:#}` by value"))
+                    .span(span)
+                    .label(format!("`{value:#}` doesn't fit in a register"))
+                    .note("HINT: Return a pointer to it instead"),
+            );
+        }
+    }
+
+    // `ret {a, b}` from a function returning several values, each is checked on its own
+    fn check_return_values(&mut self, value: &mut AST, ret: &Type) {
+        let ASTKind::HeapLiteral(values) = &mut value.kind
+        else {
+            return;
+        };
+        let expected = ret.return_values().unwrap_or_default();
+
+        if values.len() != expected.len() {
+            self.report(
+                ReportKind::InvalidReturn
+                    .title(format!(
+                        "Returning {} values from a function returning {}",
+                        values.len(),
+                        expected.len()
+                    ))
+                    .span(value.span)
+                    .label(format!("expected `{ret:#}`")),
+            );
+        }
+        for (i, value) in values.iter_mut().enumerate() {
+            let Some(expected) = expected.get(i)
+            else {
+                self.check_node(value);
+                continue;
+            };
+            let from = self.check_value(value, Some(expected));
+            let span = value.span;
+            self.check_cast(value, from.as_ref(), expected, span, false);
+        }
+    }
+
+    fn check_loop_control(&self, keyword: &str, label: Option<&str>, span: Span) {
+        if self.loops.is_empty() {
+            return self.report(
+                ReportKind::ControlOutsideLoop
+                    .title(format!("`{keyword}` outside of a loop"))
+                    .span(span),
+            );
+        }
+
+        if let Some(label) = label {
+            if !self.loops.iter().any(|name| name.as_deref() == Some(label)) {
+                self.report(
+                    ReportKind::UndefinedLoop
+                        .title(format!("No loop named '{label}' around this `{keyword}`"))
+                        .span(span)
+                        .note(format!("HINT: Name a loop with `loop {label}: ..`")),
+                );
+            }
+        }
+    }
+
+    fn check_condition(&mut self, cond: &mut AST) {
+        self.check_scalar(cond, "a condition", "HINT: Compare it against something instead");
+    }
+
+    // Gives back the type and size of the value if it fits in a register
+    fn check_scalar(&mut self, value: &mut AST, role: &str, hint: &str) -> Option<(Type, usize)> {
+        let ty = self.check_value(value, None)?;
+
+        if !ty.fits_register() {
+            self.report(
+                ReportKind::InvalidCondition
+                    .title(format!("Cannot use `{ty}` as {role}"))
+                    .span(value.span)
+                    .label(match ty.size() {
+                        Some(size) => format!("{size} bytes don't fit in a register"),
+                        None => String::from("this has no known size"),
+                    })
+                    .note(hint),
+            );
+            return None;
+        }
+        let size = Shape::of(&ty).size()?;
+        Some((ty, size))
+    }
+
+    // The type of the element `index` picks out of `base`, and whether it's behind a pointer
+    fn check_index(&mut self, base: &mut AST, index: &mut AST) -> Option<(Type, bool)> {
+        let ty = self.check_indexed(base, "index")?;
+        self.check_value(index, Some(&Type::Size(POINTER_SIZE)));
+
+        let (element, len, through_pointer) = ty.indexed()?;
+        self.check_bound(index, len, &ty, false);
+        Some((element, through_pointer))
+    }
+
+    // `a[lo..hi]` becomes `{&a[lo], hi - lo}`, the end defaults to the length of a fixed array.
+    // Broken slices are left as they are, but still give their type.
+    fn check_slice(
+        &mut self, base: &mut AST, lo: Option<&AST>, hi: Option<&AST>, span: Span,
+    ) -> Option<(Option<ASTKind>, Type)> {
+        let ty = self.check_indexed(base, "slice")?;
+        let (element, len, _) = ty.indexed()?;
+
+        let pointer = Type::Heap { is_pointer: true, contents: vec![element] };
+        let slice = Type::Heap {
+            is_pointer: false,
+            contents:   vec![pointer.clone(), Type::Size(POINTER_SIZE)],
+        };
+
+        let mut lo = lo.cloned().unwrap_or_else(|| ASTKind::IntegerLiteral(0).into_ast(span));
+        let mut hi = match (hi, len) {
+            (Some(hi), _) => hi.clone(),
+            (None, Some(len)) => ASTKind::IntegerLiteral(len).into_ast(span),
+            (None, None) => {
+                self.report(
+                    ReportKind::InvalidIndex
+                        .title(format!("Slice of `{ty:#}` needs an end"))
+                        .span(span)
+                        .label("its length isn't known")
+                        .note("HINT: Give the end after the `..`"),
+                );
+                return Some((None, slice));
+            },
+        };
+        self.check_value(&mut lo, Some(&Type::Size(POINTER_SIZE)));
+        self.check_value(&mut hi, Some(&Type::Size(POINTER_SIZE)));
+
+        // the start is used for both the address and the length
+        if lo.has_side_effects() {
+            self.report(
+                ReportKind::InvalidIndex
+                    .title("Slice start would be evaluated twice")
+                    .span(lo.span)
+                    .label("this has side effects")
+                    .note("HINT: Bind it to a name first"),
+            );
+            return Some((None, slice));
+        }
+
+        let bounds = (self.check_bound(&lo, len, &ty, true), self.check_bound(&hi, len, &ty, true));
+        let length = match bounds {
+            (Some(lo_val), Some(hi_val)) if lo_val > hi_val => {
+                self.report(
+                    ReportKind::IndexOutOfBounds
+                        .title(format!("Slice `{lo_val}..{hi_val}` ends before it starts"))
+                        .span(lo.span.extend(&hi.span)),
+                );
+                return Some((None, slice));
+            },
+            (Some(lo_val), Some(hi_val)) =>
+                ASTKind::IntegerLiteral((hi_val - lo_val).unsigned_abs() as usize),
+            _ => ASTKind::BinaryExpr(Operator::Substract, Box::new(hi), Box::new(lo.clone())),
+        };
+
+        let index = ASTKind::BinaryExpr(Operator::Index, Box::new(base.clone()), Box::new(lo));
+        let address = ASTKind::UnaryExpr(Operator::AddressOf, Box::new(index.into_ast(span)));
+        let lowered = ASTKind::HeapLiteral(vec![
+            ASTKind::TypeAnnotation(pointer, Box::new(address.into_ast(span))).into_ast(span),
+            ASTKind::TypeAnnotation(Type::Size(POINTER_SIZE), Box::new(length.into_ast(span)))
+                .into_ast(span),
+        ]);
+
+        Some((Some(lowered), slice))
+    }
+
+    // Checks something being indexed or sliced, annotating it with its type for codegen
+    fn check_indexed(&mut self, base: &mut AST, action: &str) -> Option<Type> {
+        let Some(ty) = self.check_node(base)
+        else {
+            self.report(
+                ReportKind::InvalidIndex
+                    .title(format!("Cannot {action} a value of unknown type"))
+                    .span(base.span)
+                    .note("HINT: Annotate it with what it points to, e.g. `!f<[8]>`"),
+            );
+            return None;
+        };
+
+        if ty.indexed().is_none() {
+            self.report(
+                ReportKind::InvalidIndex
+                    .title(format!("Cannot {action} `{ty:#}`"))
+                    .span(base.span)
+                    .label("this isn't an array or a pointer"),
+            );
+            return None;
+        }
+
+        annotate(base, &ty);
+        Some(ty)
+    }
+
+    // Reports constant indices past the end of `ty`, which has `len` elements if that's known.
+    // The end itself is only valid as the end of a slice.
+    fn check_bound(
+        &mut self, index: &AST, len: Option<usize>, ty: &Type, is_end: bool,
+    ) -> Option<i128> {
+        let val = self.eval_const(&mut index.clone()).ok()?;
+        let limit = len.map_or(i128::MAX, |len| len as i128 - i128::from(!is_end));
+
+        if !(0..=limit).contains(&val) {
+            self.report(
+                ReportKind::IndexOutOfBounds
+                    .title(format!("Index {val} is out of bounds for `{ty:#}`"))
+                    .span(index.span)
+                    .label(match len {
+                        Some(len) => format!("there are {len} elements"),
+                        None => String::from("indices can't be negative"),
+                    }),
+            );
+            return None;
+        }
+        Some(val)
+    }
+
+    // `a.b` names a field of a struct, `a.N` an element of a struct or heap by its position,
+    // looking through a pointer to either. A position within an array becomes an index.
+    fn check_access(&mut self, ast: &mut AST) -> Option<Type> {
+        let ASTKind::BinaryExpr(Operator::Access, base, field) = &mut ast.kind
+        else {
+            return None;
+        };
+
+        let Some(ty) = self.check_node(base)
+        else {
+            self.report(
+                ReportKind::InvalidAccess
+                    .title("Cannot access a field of a value of unknown type")
+                    .span(base.span)
+                    .note("HINT: Annotate it with what it points to, e.g. `!f<[Point]>`"),
+            );
+            return None;
+        };
+        annotate(base, &ty);
+
+        let Some((fields, is_struct)) = self.fields(&ty)
+        else {
+            return match (&field.kind, ty.indexed()) {
+                (ASTKind::IntegerLiteral(_), Some((element, len, _))) => {
+                    self.check_bound(field, len, &ty, false);
+                    let kind = std::mem::replace(&mut ast.kind, ASTKind::IntegerLiteral(0));
+                    if let ASTKind::BinaryExpr(_, base, index) = kind {
+                        ast.kind = ASTKind::BinaryExpr(Operator::Index, base, index);
+                    }
+                    Some(element)
+                },
+                _ => {
+                    self.report(
+                        ReportKind::InvalidAccess
+                            .title(format!("`{ty:#}` has no fields"))
+                            .span(base.span)
+                            .label("this isn't a struct, a heap or a pointer to one"),
+                    );
+                    None
+                },
+            };
+        };
+
+        let position = match &field.kind {
+            ASTKind::IntegerLiteral(position) if *position < fields.len() => *position,
+            ASTKind::IntegerLiteral(position) => {
+                self.report(
+                    ReportKind::UnknownField
+                        .title(format!("`{ty:#}` has no element {position}"))
+                        .span(field.span)
+                        .label(format!("there are {} elements", fields.len())),
+                );
+                return None;
+            },
+            ASTKind::Identifier(name) =>
+                match fields.iter().position(|(field, _)| field.as_deref() == Some(name)) {
+                    Some(position) => position,
+                    None => {
+                        let names = fields.iter().filter_map(|(field, _)| field.as_deref());
+                        let note = match (closest(name, names), is_struct) {
+                            (Some(similar), _) => format!("HINT: Did you mean '{similar}'?"),
+                            (None, true) => {
+                                let names: Vec<_> =
+                                    fields.iter().filter_map(|(field, _)| field.clone()).collect();
+                                format!("HINT: Its fields are {}", names.join(", "))
+                            },
+                            (None, false) => String::from(
+                                "HINT: Elements of a heap are named by position, e.g. `.0`",
+                            ),
+                        };
+                        self.report(
+                            ReportKind::UnknownField
+                                .title(format!("`{ty:#}` has no field '{name}'"))
+                                .span(field.span)
+                                .note(note),
+                        );
+                        return None;
+                    },
+                },
+            _ => return None,
+        };
+        Some(fields[position].1.clone())
+    }
+
+    // The elements of a struct or heap along with their names if it's a struct, looking through
+    // a single pointer
+    fn fields(&self, ty: &Type) -> Option<(Fields, bool)> {
+        match ty.expanded() {
+            Type::Struct(name, _) => self.structs.get(name).map(|info| {
+                let fields = info.fields.iter().map(|(name, ty)| (Some(name.clone()), ty.clone()));
+                (fields.collect(), true)
+            }),
+            Type::Heap { is_pointer: false, contents } =>
+                Some((contents.iter().map(|ty| (None, ty.clone())).collect(), false)),
+            Type::Heap { is_pointer: true, .. } => {
+                let pointee = ty.pointee()?;
+                match pointee.expanded() {
+                    Type::Struct(..) | Type::Heap { is_pointer: false, .. } =>
+                        self.fields(&pointee),
+                    _ => None,
+                }
+            },
+            Type::Register { inner: Some(inner), .. } => self.fields(inner),
+            _ => None,
+        }
+    }
+
+    // The binding a field or element is stored within, `None` if it's reached through a pointer
+    fn place_root<'a>(&self, ast: &'a AST) -> Option<&'a AST> {
+        match &ast.kind {
+            ASTKind::Identifier(_) => Some(ast),
+            ASTKind::TypeAnnotation(_, inner) => self.place_root(inner),
+            ASTKind::BinaryExpr(Operator::Access | Operator::Index, base, _) => {
+                let ty = match &base.kind {
+                    ASTKind::Identifier(name) => &self.lookup(name)?.ty,
+                    ASTKind::TypeAnnotation(ty, _) => ty,
+                    _ => return None,
+                };
+                match ty.pointee() {
+                    Some(_) => None,
+                    None => self.place_root(base),
+                }
+            },
+            _ => None,
+        }
+    }
+
+    // Folds every pattern into `ranges`, the match is exhaustive if they cover every value the
+    // matched one can have, or every variant of the enum it has or the patterns name.
+    fn check_match(
+        &mut self, value: &mut AST, arms: &mut [MatchArm], is_exhaustive: &mut bool,
+    ) -> Option<Type> {
+        let (matched, size) = self
+            .check_scalar(value, "a match value", "HINT: Match on one part of it instead")
+            .map_or((None, POINTER_SIZE), |(ty, size)| (Some(ty), size));
+
+        // patterns are variants of the matched enum before any other
+        let outer = std::mem::replace(&mut self.expected, matched);
+        let mut covered: Vec<(i128, i128)> = Vec::new();
+        let mut matched_enum = self.expected_enum().map(str::to_string);
+        for arm in arms.iter_mut() {
+            arm.ranges.clear();
+            for pattern in &mut arm.patterns {
+                let (lo, hi) = match pattern {
+                    Pattern::Wildcard(_) => continue,
+                    Pattern::Value(value) => {
+                        matched_enum = matched_enum.or_else(|| self.variant_owner(value));
+                        let Some(value) = self.eval_pattern(value, size)
+                        else {
+                            continue;
+                        };
+                        (value, value)
+                    },
+                    Pattern::Range(lo, hi) => {
+                        matched_enum = matched_enum.or_else(|| self.variant_owner(lo));
+                        let (Some(lo_val), Some(hi_val)) =
+                            (self.eval_pattern(lo, size), self.eval_pattern(hi, size))
+                        else {
+                            continue;
+                        };
+
+                        if lo_val > hi_val {
+                            self.report(
+                                ReportKind::InvalidPattern
+                                    .title(format!("Range `{lo_val}..{hi_val}` matches nothing"))
+                                    .span(lo.span.extend(&hi.span))
+                                    .note(format!("HINT: Write it as `{hi_val}..{lo_val}`")),
+                            );
+                            continue;
+                        }
+                        (lo_val, hi_val)
+                    },
+                };
+
+                // negative values are matched by their bits, so a range across zero wraps around
+                let (lo, hi) = (truncate(lo, size), truncate(hi, size));
+                let ranges = match lo <= hi {
+                    true => vec![(lo, hi)],
+                    false => vec![(0, hi), (lo, max_unsigned(size))],
+                };
+
+                if ranges.iter().all(|&(lo, hi)| covers(&covered, lo, hi)) {
+                    self.report(
+                        ReportKind::UnreachablePattern
+                            .title("Pattern is already matched above")
+                            .span(pattern.span())
+                            .label("this never matches"),
+                    );
+                }
+
+                covered.extend(&ranges);
+                arm.ranges.extend(
+                    ranges
+                        .iter()
+                        .map(|&(lo, hi)| (lo.unsigned_abs() as usize, hi.unsigned_abs() as usize)),
+                );
+            }
+        }
+
+        self.expected = outer;
+        if !*is_exhaustive {
+            *is_exhaustive = self.check_exhaustive(&covered, size, matched_enum, value.span);
+        }
+
+        let mut types =
+            arms.iter_mut().map(|arm| self.check_node(&mut arm.body)).collect::<Vec<_>>();
+        let first = types.pop()?;
+        match *is_exhaustive && types.iter().all(|ty| *ty == first) {
+            true => first,
+            false => None,
+        }
+    }
+
+    fn check_exhaustive(
+        &self, covered: &[(i128, i128)], size: usize, matched_enum: Option<String>, span: Span,
+    ) -> bool {
+        if covers(covered, 0, max_unsigned(size)) {
+            return true;
+        }
+
+        let Some(info) = matched_enum.as_ref().and_then(|name| self.enums.get(name))
+        else {
+            return false;
+        };
+
+        let missing = info
+            .variants
+            .iter()
+            .filter(|variant| {
+                let value = truncate(variant.value, size);
+                !covers(covered, value, value)
+            })
+            .map(|variant| format!("'{}'", variant.name))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            self.report(
+                ReportKind::NonExhaustiveMatch
+                    .title(format!(
+                        "Match on '{}' doesn't cover {}",
+                        matched_enum.unwrap_or_default(),
+                        missing.join(", ")
+                    ))
+                    .span(span)
+                    .note("HINT: Add an arm for them, or a `_` arm for anything left"),
+            );
+        }
+        true
+    }
+
+    // The enum `pattern` is a variant of, unless a binding shadows it
+    fn variant_owner(&self, pattern: &AST) -> Option<String> {
+        match &pattern.kind {
+            ASTKind::Identifier(name) if self.lookup(name).is_none() =>
+                self.variant(name).map(|variant| variant.owner.clone()),
+            _ => None,
+        }
+    }
+
+    fn eval_pattern(&mut self, pattern: &mut AST, size: usize) -> Option<i128> {
+        let value = match self.eval_const(pattern) {
+            Ok(value) => value,
+            Err(report) => {
+                self.report(*report);
+                return None;
+            },
+        };
+
+        if !fits(value, size) {
+            self.report(
+                ReportKind::InvalidPattern
+                    .title(format!("Pattern {value} doesn't fit in {size} bytes"))
+                    .span(pattern.span)
+                    .label("the matched value is never this"),
+            );
+            return None;
+        }
+        Some(value)
+    }
+
+    fn check_destructure(
+        &mut self, bindings: &mut [DestructureBinding], value: &mut AST, span: Span,
+    ) {
+        for binding in bindings.iter_mut() {
+            if let Some(ty) = &mut binding.ty {
+                self.resolve_type(ty, binding.span);
+            }
+        }
+
+        // what each name is bound to, `None` for untyped literals
+        let (elements, shape) = match &mut value.kind {
+            ASTKind::HeapLiteral(values) => {
+                let elements = values
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let ty = bindings.get(i).and_then(|b| b.ty.clone());
+                        self.check_value(value, ty.as_ref())
+                    })
+                    .collect::<Vec<_>>();
+                (elements, String::from("this heap"))
+            },
+            _ => match self.check_node(value) {
+                Some(ty) => match self.elements(&ty) {
+                    Some(elements) =>
+                        (elements.into_iter().map(Some).collect(), format!("`{ty:#}`")),
+                    // already reported as undefined
+                    None if matches!(ty.expanded(), Type::Struct(..)) =>
+                        (Vec::new(), String::new()),
+                    None => {
+                        self.report(
+                            ReportKind::InvalidDestructure
+                                .title(format!("Cannot destructure `{ty:#}`"))
+                                .span(value.span)
+                                .label("this isn't a heap or struct"),
+                        );
+                        (Vec::new(), String::new())
+                    },
+                },
+                None => {
+                    self.report(
+                        ReportKind::InvalidDestructure
+                            .title("Cannot destructure a value of unknown type")
+                            .span(value.span)
+                            .label("this isn't known to be a heap or struct")
+                            .note("HINT: Annotate it with the heap it holds, e.g. `!f<{8, 8}>`"),
+                    );
+                    (Vec::new(), String::new())
+                },
+            },
+        };
+
+        if !shape.is_empty() && elements.len() != bindings.len() {
+            let plural = |n: usize| if n == 1 { "" } else { "s" };
+            self.report(
+                ReportKind::DestructureArity
+                    .title(format!(
+                        "Destructuring {} element{} into {} name{}",
+                        elements.len(),
+                        plural(elements.len()),
+                        bindings.len(),
+                        plural(bindings.len())
+                    ))
+                    .span(span)
+                    .label(format!(
+                        "{shape} has {} element{}",
+                        elements.len(),
+                        plural(elements.len())
+                    ))
+                    .note("HINT: Every element needs exactly one name"),
+            );
+        }
+
+        for (i, binding) in bindings.iter_mut().enumerate() {
+            let element = elements.get(i).cloned().flatten();
+            let ty = match (&binding.ty, element) {
+                (Some(ty), Some(element)) => {
+                    let fits = ty.fits_register() && element.fits_register();
+                    if !fits && ty.size() != element.size() {
+                        self.report(
+                            ReportKind::InvalidCast
+                                .title(format!("Cannot bind `{element:#}` to `{ty:#}`"))
+                                .span(binding.span)
+                                .label(format!("'{}' is given the wrong type", binding.name))
+                                .note("HINT: Leave the type out to take the element's own"),
+                        );
+                    }
+                    ty.clone()
+                },
+                (Some(ty), None) => ty.clone(),
+                (None, Some(element)) => element,
+                (None, None) => Type::Size(POINTER_SIZE),
+            };
+            binding.ty = Some(ty.clone());
+
+            self.scopes.last_mut().expect("Checker has no scope").insert(
+                binding.name.clone(),
+                Binding { span: binding.span, ty, is_mutable: binding.is_mutable, constant: None },
+            );
+        }
+    }
+
+    // The types laid out one after another within a heap or struct
+    fn elements(&self, ty: &Type) -> Option<Vec<Type>> {
+        match ty.expanded() {
+            Type::Heap { contents, .. } => Some(contents.clone()),
+            Type::Struct(name, _) => self
+                .structs
+                .get(name)
+                .map(|info| info.fields.iter().map(|(_, ty)| ty.clone()).collect()),
+            Type::Register { inner: Some(inner), .. } => self.elements(inner),
+            _ => None,
+        }
+    }
+
+    fn check_operator(
+        &mut self, label: &str, op: &Operator, params: &mut [Parameter], ret: &mut Type, span: Span,
+    ) {
+        for param in params.iter_mut() {
+            self.resolve_type(&mut param.ty, param.span);
+        }
+        self.resolve_type(ret, span);
+        self.check_return_type(ret, span);
+
+        let symbol = operator_symbol(op);
+        if params.iter().all(|param| param.ty.fits_register()) {
+            return self.report(
+                ReportKind::InvalidOperator
+                    .title(format!("`{symbol}` can't be redefined for scalar operands"))
+                    .span(span)
+                    .label("none of the operands is a struct or heap")
+                    .note("HINT: Operators can only be defined for structs and heaps"),
+            );
+        }
+
+        let params = params.iter().map(|param| param.ty.expanded().clone()).collect::<Vec<_>>();
+        if let Some(first) =
+            self.operators.iter().find(|overload| overload.op == *op && overload.params == params)
+        {
+            return self.report(
+                ReportKind::DuplicateOperator
+                    .title(format!("`{symbol}` is already defined for these operands"))
+                    .span(span)
+                    .label("redefined here")
+                    .info(format!("first defined at {}", first.span)),
+            );
+        }
+
+        self.operators.push(Overload {
+            op: op.clone(),
+            params,
+            ret: ret.clone(),
+            label: label.to_string(),
+            span,
+        });
+    }
+
+    // The user defined operator applying to `operands`, only looked for if any is a struct or heap.
+    // Those have no builtin meaning, so not finding one is an error.
+    fn find_overload(
+        &self, op: &Operator, operands: &[Option<Type>], span: Span,
+    ) -> Option<&Overload> {
+        // type parameters always stand for something scalar
+        if operands.iter().flatten().all(|ty| ty.fits_register() || self.is_generic_type(ty)) {
+            return None;
+        }
+
+        let applies = |overload: &&Overload| {
+            overload.op == *op
+                && overload.params.len() == operands.len()
+                && overload.params.iter().zip(operands).all(|(param, operand)| match operand {
+                    Some(operand) => operand.expanded() == param,
+                    // untyped literals go into anything scalar
+                    None => param.fits_register(),
+                })
+        };
+        if let Some(overload) = self.operators.iter().find(applies) {
+            return Some(overload);
+        }
+
+        let symbol = operator_symbol(op);
+        let operands = operands
+            .iter()
+            .map(|ty| {
+                ty.as_ref().map_or_else(|| String::from("an integer"), |ty| format!("`{ty:#}`"))
+            })
+            .collect::<Vec<_>>();
+
+        let mut report = ReportKind::UndefinedOperator
+            .title(format!("`{symbol}` isn't defined for {}", operands.join(" and ")))
+            .span(span);
+        for overload in self.operators.iter().filter(|overload| overload.op == *op) {
+            let params = overload.params.iter().map(ToString::to_string).collect::<Vec<_>>();
+            report = report.info(format!(
+                "`{symbol}` is defined for ({}) at {}",
+                params.join(", "),
+                overload.span
+            ));
+        }
+        self.report(
+            report.note(format!("HINT: Define it with `op {symbol} (...) <type> {{ ... }}`")),
+        );
+        None
+    }
+
+    fn check_value(&mut self, value: &mut AST, expected: Option<&Type>) -> Option<Type> {
+        let outer = std::mem::replace(&mut self.expected, expected.cloned());
+        let ty = self.check_node(value);
+        self.expected = outer;
+        ty
+    }
+
+    // The enum whatever is being checked is stored into, its variants come first
+    fn expected_enum(&self) -> Option<&str> {
+        match self.expected.as_ref()? {
+            Type::Alias(name, _) if self.enums.contains_key(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    // The variant `name` refers to, from the expected enum if it has one by that name, otherwise
+    // from the only enum that does
+    fn variant(&self, name: &str) -> Option<&Variant> {
+        let find = |owner: &str| self.enums.get(owner)?.variants.iter().find(|v| v.name == name);
+        if let Some(variant) = self.expected_enum().and_then(find) {
+            return Some(variant);
+        }
+
+        match self.variants.get(name)?.as_slice() {
+            [owner] => find(owner),
+            _ => None,
+        }
+    }
+
+    // Reports `name` if it's a variant of several enums, none of which is expected here
+    fn check_ambiguous(&self, name: &str, span: Span) -> bool {
+        let Some(owners) = self.variants.get(name).filter(|owners| owners.len() > 1)
+        else {
+            return false;
+        };
+
+        let quoted = owners.iter().map(|owner| format!("'{owner}'")).collect::<Vec<_>>();
+        self.report(
+            ReportKind::AmbiguousVariant
+                .title(format!("Variant '{name}' is defined by {}", quoted.join(" and ")))
+                .span(span)
+                .label("which one can't be told from here")
+                .note(format!("HINT: Store it somewhere typed, e.g. `x {} <- {name}`", owners[0])),
+        );
+        true
+    }
+
+    // Catches constant arithmetic which is guaranteed to go wrong at runtime.
+    fn check_arithmetic(&mut self, op: &Operator, lhs: &mut AST, rhs: &mut AST, size: usize) {
+        if !matches!(
+            op,
+            Operator::Add
+                | Operator::Substract
+                | Operator::Multiply
+                | Operator::Divide
+                | Operator::Modulo
+                | Operator::ShiftL
+                | Operator::ShiftR
+        ) {
+            return;
+        }
+
+        let (Ok(lhs_val), Ok(rhs_val)) = (self.eval_const(lhs), self.eval_const(rhs))
+        else {
+            return;
+        };
+
+        let bits = size * 8;
+        match op {
+            Operator::Divide | Operator::Modulo if rhs_val == 0 =>
+                self.report(self.division_by_zero(op, lhs, rhs)),
+            Operator::ShiftL | Operator::ShiftR if !(0..bits as i128).contains(&rhs_val) => self
+                .report(
+                    ReportKind::ShiftOverflow
+                        .title(format!(
+                            "Shift by {rhs_val} bits is too wide for a {size} byte value"
+                        ))
+                        .span(self.operator_span(op, lhs, rhs))
+                        .label("this shift")
+                        .note(format!(
+                            "HINT: Only shifts of 0 to {} bits are meaningful",
+                            bits - 1
+                        )),
+                ),
+            Operator::Divide | Operator::Modulo | Operator::ShiftR => {},
+            _ => {
+                if fold(op, lhs_val, rhs_val).is_some_and(|val| fits(val, size)) {
+                    return;
+                }
+
+                let symbol = op.infix_symbol().unwrap_or_default();
+                self.report(
+                    ReportKind::ConstantOverflow
+                        .title(format!("Constant arithmetic overflows a {size} byte value"))
+                        .span(self.operator_span(op, lhs, rhs))
+                        .label(format!("`{lhs_val} {symbol} {rhs_val}` doesn't fit"))
+                        .note("HINT: The result wraps around at runtime"),
+                );
+            },
+        }
+    }
+
+    // Integers are unsigned, so `->` zero extends and warns when data may be lost. `cast` reads
+    // its operand as signed, sign extending it, and never warns. Heaps and arrays may only be
+    // reinterpreted as something of the same size.
+    fn check_cast(
+        &mut self, value: &mut AST, from: Option<&Type>, to: &Type, span: Span, is_explicit: bool,
+    ) {
+        // nothing is known about a type parameter until the function is instantiated
+        if from.is_some_and(|from| self.is_generic_type(from)) || self.is_generic_type(to) {
+            return;
+        }
+
+        let (from_size, to_size) = match (from.map(Shape::of), Shape::of(to)) {
+            (Some(Shape::Pointer), Shape::Scalar(to)) => (Some(POINTER_SIZE), to),
+            (Some(Shape::Scalar(from)), Shape::Pointer) => (Some(from), POINTER_SIZE),
+            (Some(Shape::Scalar(from)), Shape::Scalar(to)) => (Some(from), to),
+            // untyped literals only have to fit
+            (None, Shape::Scalar(to)) => (None, to),
+            (Some(Shape::Pointer), Shape::Pointer) | (None, _) => return,
+
+            (Some(from_shape), to_shape) => {
+                let (from_size, to_size) = (from_shape.size(), to_shape.size());
+                if from_size.is_some() && from_size == to_size {
+                    return;
+                }
+
+                let from = from.expect("shape of a missing type");
+                let label = match (from_size, to_size) {
+                    (Some(from_size), Some(to_size)) =>
+                        format!("{from_size} bytes can't become {to_size}"),
+                    (None, _) => format!("`{from:#}` has no fixed size"),
+                    (_, None) => format!("`{to:#}` has no fixed size"),
+                };
+
+                self.report(
+                    ReportKind::InvalidCast
+                        .title(format!("Cannot cast `{from:#}` to `{to:#}`"))
+                        .span(span)
+                        .label(label)
+                        .note("HINT: Heaps and arrays can only be cast to and from types of the same size"),
+                );
+                return;
+            },
+        };
+
+        if is_explicit || from_size.is_some_and(|from_size| from_size <= to_size) {
+            return;
+        }
+
+        // a constant loses nothing if it fits
+        match self.eval_const(value) {
+            Ok(val) if fits(val, to_size) => return,
+            Err(_) if from_size.is_none() => return,
+            _ => {},
+        }
+
+        let from = from.map_or_else(|| String::from("an integer"), |from| format!("`{from:#}`"));
+        self.report(
+            ReportKind::LossyCast
+                .title(format!("Cast from {from} to `{to:#}` may lose data"))
+                .span(span)
+                .label(format!(
+                    "truncated to {to_size} byte{}",
+                    if to_size == 1 { "" } else { "s" }
+                ))
+                .note(format!("HINT: Use `cast {to} ..` if the truncation is intended")),
+        );
+    }
+
+    fn division_by_zero(&self, op: &Operator, lhs: &AST, rhs: &AST) -> Report {
+        ReportKind::DivisionByZero
+            .title(match op {
+                Operator::Modulo => "Modulo by zero",
+                _ => "Division by zero",
+            })
+            .span(self.operator_span(op, lhs, rhs))
+            .label("the right side is always zero")
+    }
+
+    // The AST doesn't keep operator tokens around, so look for it between the operands.
+    fn operator_span(&self, op: &Operator, lhs: &AST, rhs: &AST) -> Span {
+        let whole = lhs.span.extend(&rhs.span);
+        let (line_number, start) = lhs.span.end();
+        let Some(symbol) = op.infix_symbol().filter(|_| line_number == rhs.span.line_number)
+        else {
+            return whole;
+        };
+
+        Scanner::get(self.filename)
+            .lines()
+            .nth(line_number - 1)
+            .and_then(|line| line.get(start..rhs.span.offset))
+            .and_then(|gap| gap.find(symbol))
+            .map_or(whole, |i| whole.offset(start + i).len(symbol.len()))
+    }
+
+    // Checks that `target` names a place which may be written to, returning its type.
+    // `site` is the span of the whole mutating expression.
+    fn check_mutation(&mut self, target: &mut AST, site: Span) -> Option<Type> {
+        match &mut target.kind {
+            ASTKind::Identifier(name) => match self.lookup(name) {
+                Some(binding) if binding.is_mutable => Some(binding.ty.clone()),
+                Some(binding) => {
+                    self.report(
+                        ReportKind::ImmutableMutation
+                            .title(format!("Cannot mutate immutable binding '{name}'"))
+                            .span(binding.span.len(1).ghost('\''))
+                            .label("consider making this binding mutable")
+                            .info(format!("mutated at {site}")),
+                    );
+                    Some(binding.ty.clone())
+                },
+                None => {
+                    self.report(
+                        ReportKind::UndefinedVariable
+                            .title(format!("Cannot mutate undefined binding '{name}'"))
+                            .span(target.span),
+                    );
+                    None
+                },
+            },
+
+            // writing through a pointer doesn't touch the pointer itself
+            ASTKind::UnaryExpr(Operator::Deref, inner) =>
+                self.check_node(inner).and_then(|ty| ty.pointee()),
+
+            // fields and elements are part of the binding holding them, unlike ones behind a pointer
+            ASTKind::BinaryExpr(Operator::Access | Operator::Index, ..) => {
+                let element = self.check_node(target)?;
+                if let Some(root) = self.place_root(target) {
+                    let mut root = root.clone();
+                    self.check_mutation(&mut root, site);
+                }
+                Some(element)
+            },
+            ASTKind::TypeAnnotation(ty @ Type::Register { .. }, base) => {
+                self.check_mutation(base, site);
+                Some(ty.clone())
+            },
+
+            _ => {
+                self.report(
+                    ReportKind::InvalidAssignmentTarget
+                        .title("Invalid assignment target")
+                        .span(target.span)
+                        .label("this is not an assignable place")
+                        .note(
+                            "HINT: Only bindings, dereferences, fields and registers can be mutated",
+                        ),
+                );
+                None
+            },
+        }
+    }
+
+    // Evaluates every array length within `ty`, storing the results in place.
+    // `span` is wherever the type was written, for reporting undefined types.
+    fn resolve_type(&mut self, ty: &mut Type, span: Span) {
+        match ty {
+            Type::Array { inner, elems } => {
+                self.resolve_type(inner, span);
+
+                let Some(ArrayLength::Expr(expr, len @ None)) = elems
+                else {
+                    return;
+                };
+
+                match self.eval_const(expr) {
+                    Ok(val) if val > 0 => match usize::try_from(val) {
+                        Ok(val) => *len = Some(val),
+                        Err(_) => self.report(
+                            ReportKind::InvalidArrayLength
+                                .title(format!("Array length {val} is too large"))
+                                .span(expr.span),
+                        ),
+                    },
+                    Ok(val) => self.report(
+                        ReportKind::InvalidArrayLength
+                            .title(format!("Array length must be positive, found {val}"))
+                            .span(expr.span)
+                            .note(format!("HINT: Did you mean [{inner}:]")),
+                    ),
+                    Err(report) => self.report(*report),
+                }
+            },
+            Type::Heap { contents, .. } =>
+                contents.iter_mut().for_each(|ty| self.resolve_type(ty, span)),
+            Type::Register { inner: Some(inner), .. } => self.resolve_type(inner, span),
+            Type::Struct(name, None) if self.type_params.contains(name) => {},
+            Type::Struct(name, size @ None) => match self.structs.get(name) {
+                Some(info) => *size = info.size,
+                // enums are integers, named so their variants can be told apart
+                None => match self.enums.get(name) {
+                    Some(info) => *ty = Type::Alias(name.clone(), Box::new(Type::Size(info.size))),
+                    None if self.aliases.contains_key(name) => {
+                        let name = name.clone();
+                        self.expand_alias(&name);
+                        if let Some(Alias::Expanded(_, Some(expanded))) = self.aliases.get(&name) {
+                            *ty = Type::Alias(name, Box::new(expanded.clone()));
+                        }
+                    },
+                    None => self.report(
+                        ReportKind::UndefinedType
+                            .title(format!("Undefined type '{name}'"))
+                            .span(span)
+                            .note("HINT: Types have to be defined before they're used"),
+                    ),
+                },
+            },
+            Type::Size(_)
+            | Type::Struct(..)
+            | Type::Alias(..)
+            | Type::Register { inner: None, .. } => {},
+        }
+    }
+
+    // Errors are returned rather than reported, not every caller needs a constant.
+    fn eval_const(&mut self, ast: &mut AST) -> Result<i128> {
+        let span = ast.span;
+        let not_constant = |label: &str| {
+            ReportKind::NonConstantExpression
+                .title("Expected a constant expression")
+                .span(span)
+                .label(label)
+        };
+
+        let val = match &mut ast.kind {
+            ASTKind::IntegerLiteral(val) => *val as i128,
+            ASTKind::CharLiteral(val) => i128::from(u32::from(*val)),
+
+            ASTKind::Identifier(name) => match self.lookup(name) {
+                Some(Binding { constant: Some(val), .. }) => *val,
+                Some(Binding { is_mutable: true, .. }) => {
+                    return not_constant("this binding is mutable")
+                        .note(format!("HINT: Remove the `'` from '{name}' to make it constant"))
+                        .as_err();
+                },
+                Some(_) =>
+                    return not_constant("this binding isn't initialised with a constant").as_err(),
+                None => match self.variant(name) {
+                    Some(variant) => variant.value,
+                    None if self.variants.contains_key(name) =>
+                        return not_constant("this is a variant of several enums").as_err(),
+                    None => return not_constant("this isn't defined").as_err(),
+                },
+            },
+
+            ASTKind::SizeOf(ty) => {
+                self.resolve_type(ty, span);
+                match ty.size() {
+                    Some(size) => size as i128,
+                    None => return not_constant("this type has no fixed size").as_err(),
+                }
+            },
+
+            ASTKind::TypeAnnotation(_, operand)
+            | ASTKind::UnaryExpr(Operator::Positive, operand) => self.eval_const(operand)?,
+            ASTKind::Cast(ty, operand, is_explicit) => {
+                let mut val = self.eval_const(operand)?;
+                // what `cast` is given is read as signed, which only matters if it's narrower
+                let from = match &operand.kind {
+                    ASTKind::Identifier(name) =>
+                        self.lookup(name).map(|binding| Shape::of(&binding.ty)),
+                    ASTKind::Cast(from, ..) | ASTKind::TypeAnnotation(from, _) =>
+                        Some(Shape::of(from)),
+                    _ => None,
+                };
+                if let (true, Some(Shape::Scalar(from @ (1 | 2 | 4)))) = (*is_explicit, from) {
+                    val = sign_extend(val, from);
+                }
+                match Shape::of(ty) {
+                    Shape::Scalar(size) => truncate(val, size),
+                    _ => val,
+                }
+            },
+            ASTKind::UnaryExpr(Operator::Negative, operand) =>
+                match self.eval_const(operand)?.checked_neg() {
+                    Some(val) => val,
+                    None =>
+                        return ReportKind::ConstantOverflow
+                            .title("Constant arithmetic overflows while negating")
+                            .span(span)
+                            .label("this can't be negated")
+                            .as_err(),
+                },
+            ASTKind::UnaryExpr(Operator::Not, operand) => !self.eval_const(operand)?,
+
+            ASTKind::BinaryExpr(op, lhs_ast, rhs_ast) => {
+                let (lhs, rhs) = (self.eval_const(lhs_ast)?, self.eval_const(rhs_ast)?);
+                if matches!(op, Operator::Divide | Operator::Modulo) && rhs == 0 {
+                    return self.division_by_zero(op, lhs_ast, rhs_ast).as_err();
+                }
+
+                match fold(op, lhs, rhs) {
+                    Some(val) => val,
+                    None =>
+                        return not_constant("this can't be evaluated at compile time").as_err(),
+                }
+            },
+
+            _ => return not_constant("this can't be evaluated at compile time").as_err(),
+        };
+
+        Ok(val)
+    }
+}
+
+// How a type behaves as a value, for deciding which casts make sense
+enum Shape {
+    Scalar(usize),
+    Pointer,
+    Aggregate(Option<usize>),
+}
+
+impl Shape {
+    fn of(ty: &Type) -> Self {
+        match ty {
+            // a register holds a value of its inner type
+            Type::Register { inner: Some(inner), .. } => Self::of(inner),
+            Type::Register { inner: None, .. } => Self::Scalar(POINTER_SIZE),
+            Type::Alias(_, ty) => Self::of(ty),
+            Type::Heap { is_pointer: true, .. } => Self::Pointer,
+            Type::Size(size) if *size <= POINTER_SIZE => Self::Scalar(*size),
+            ty => Self::Aggregate(ty.size()),
+        }
+    }
+
+    fn size(&self) -> Option<usize> {
+        match self {
+            Self::Scalar(size) => Some(*size),
+            Self::Pointer => Some(POINTER_SIZE),
+            Self::Aggregate(size) => *size,
+        }
+    }
+}
+
+// Replaces the operator with an internal call to the routine implementing it,
+// structs and heaps are passed by reference.
+fn lower_operator(ast: &mut AST, operands: &[Option<Type>], overload: &Overload) -> Type {
+    let args = match std::mem::replace(&mut ast.kind, ASTKind::Block(Vec::new())) {
+        ASTKind::BinaryExpr(_, lhs, rhs) => vec![*lhs, *rhs],
+        ASTKind::UnaryExpr(_, operand) => vec![*operand],
+        kind => unreachable!("lowering {kind:?} as an operator"),
+    };
+
+    let args = args
+        .into_iter()
+        .zip(operands)
+        .map(|(arg, ty)| match ty.as_ref().is_some_and(|ty| !ty.fits_register()) {
+            true => {
+                let span = arg.span;
+                ASTKind::UnaryExpr(Operator::AddressOf, Box::new(arg)).into_ast(span)
+            },
+            false => arg,
+        })
+        .collect();
+
+    let callee = ASTKind::Identifier(overload.label.clone()).into_ast(ast.span);
+    let call = ASTKind::Call(Box::new(callee), args, false).into_ast(ast.span);
+    ast.kind = ASTKind::TypeAnnotation(overload.ret.clone(), Box::new(call));
+    overload.ret.clone()
+}
+
+fn operator_symbol(op: &Operator) -> &'static str {
+    match op {
+        Operator::Negative => "-",
+        Operator::Not => "~",
+        op => op.infix_symbol().unwrap_or_default(),
+    }
+}
+
+// `None` if `op` can't be folded, or the result doesn't fit an i128
+fn fold(op: &Operator, lhs: i128, rhs: i128) -> Option<i128> {
+    // these see what ends up in a register, where a negative constant is a huge unsigned one
+    let (unsigned_lhs, unsigned_rhs) = (truncate(lhs, POINTER_SIZE), truncate(rhs, POINTER_SIZE));
+    match op {
+        Operator::Add => lhs.checked_add(rhs),
+        Operator::Substract => lhs.checked_sub(rhs),
+        Operator::Multiply => lhs.checked_mul(rhs),
+        Operator::Divide => unsigned_lhs.checked_div(unsigned_rhs),
+        Operator::Modulo => unsigned_lhs.checked_rem(unsigned_rhs),
+        // nothing at runtime is wider than 64 bits, so neither are the shifts worth folding.
+        // `checked_shl` only minds the shift amount, a multiply catches bits shifted out the top.
+        Operator::ShiftL => u32::try_from(rhs)
+            .ok()
+            .filter(|rhs| *rhs < 64)
+            .and_then(|rhs| lhs.checked_mul(1 << rhs)),
+        Operator::ShiftR => u32::try_from(rhs)
+            .ok()
+            .filter(|rhs| *rhs < 64)
+            .and_then(|rhs| unsigned_lhs.checked_shr(rhs)),
+        Operator::And => Some(lhs & rhs),
+        Operator::Or => Some(lhs | rhs),
+        Operator::Xor => Some(lhs ^ rhs),
+        Operator::Eq => Some(i128::from(unsigned_lhs == unsigned_rhs)),
+        Operator::Neq => Some(i128::from(unsigned_lhs != unsigned_rhs)),
+        Operator::Lt => Some(i128::from(unsigned_lhs < unsigned_rhs)),
+        Operator::Le => Some(i128::from(unsigned_lhs <= unsigned_rhs)),
+        Operator::Gt => Some(i128::from(unsigned_lhs > unsigned_rhs)),
+        Operator::Ge => Some(i128::from(unsigned_lhs >= unsigned_rhs)),
+        _ => None,
+    }
+}
+
+// Keeps the low `size` bytes
+fn truncate(val: i128, size: usize) -> i128 {
+    val & ((1 << (size * 8)) - 1)
+}
+
+// Reads the low `size` bytes back as signed, spreading the top bit
+fn sign_extend(val: i128, size: usize) -> i128 {
+    let bits = size * 8;
+    let val = truncate(val, size);
+    match val >> (bits - 1) == 1 {
+        true => val - (1 << bits),
+        false => val,
+    }
+}
+
+// Whether the type parameter `name` appears anywhere within `ty`
+fn mentions(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Struct(param, None) => param == name,
+        Type::Heap { contents, .. } => contents.iter().any(|ty| mentions(ty, name)),
+        Type::Array { inner, .. } | Type::Register { inner: Some(inner), .. } =>
+            mentions(inner, name),
+        _ => false,
+    }
+}
+
+// Matches `declared`, written in terms of `generics`, against the `actual` type in its place,
+// noting what each type parameter stands for the first time it's seen
+fn infer(declared: &Type, actual: &Type, generics: &[String], found: &mut [Option<Type>]) {
+    // a value held in a register is still a value of the type within
+    let actual = match actual {
+        Type::Register { inner: Some(inner), .. } => inner,
+        actual => actual,
+    };
+
+    match (declared, actual.expanded()) {
+        (Type::Struct(name, None), _) => {
+            let Some(i) = generics.iter().position(|param| param == name)
+            else {
+                return;
+            };
+            if found[i].is_none() {
+                found[i] = Some(actual.clone());
+            }
+        },
+        (
+            Type::Heap { is_pointer, contents },
+            Type::Heap { is_pointer: actual_pointer, contents: actual_contents },
+        ) if is_pointer == actual_pointer && contents.len() == actual_contents.len() => contents
+            .iter()
+            .zip(actual_contents)
+            .for_each(|(declared, actual)| infer(declared, actual, generics, found)),
+        (Type::Array { inner, .. }, Type::Array { inner: actual_inner, .. }) =>
+            infer(inner, actual_inner, generics, found),
+        (Type::Register { inner: Some(inner), .. }, _) => infer(inner, actual, generics, found),
+        _ => {},
+    }
+}
+
+// Checked bases of fields and indices are annotated with their type, so the generator knows
+// how to reach into them
+fn annotate(base: &mut AST, ty: &Type) {
+    if !matches!(base.kind, ASTKind::Identifier(_) | ASTKind::TypeAnnotation(..)) {
+        let inner = std::mem::replace(&mut base.kind, ASTKind::IntegerLiteral(0));
+        base.kind = ASTKind::TypeAnnotation(ty.clone(), Box::new(inner.into_ast(base.span)));
+    }
+}
+
+// Calls returning several values are marked with what they return, codegen reads them out of
+// the return registers
+fn annotate_values(call: &mut AST, ret: Option<&Type>) {
+    if let Some(ret) = ret.filter(|ret| ret.return_values().is_some()) {
+        annotate(call, ret);
+    }
+}
+
+// The candidate closest to `name`, if one is close enough to be a typo of it
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= name.len().max(candidate.len()).div_ceil(3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// The number of single character edits that turn `a` into `b`
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + usize::from(a != *b));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn max_unsigned(size: usize) -> i128 {
+    (1 << (size.min(POINTER_SIZE) * 8)) - 1
+}
+
+// Whether every value from `lo` to `hi` is within one of `ranges`
+fn covers(ranges: &[(i128, i128)], lo: i128, hi: i128) -> bool {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable();
+
+    let mut next = lo;
+    for (start, end) in ranges {
+        if start > next {
+            break;
+        }
+        next = next.max(end + 1);
+    }
+    next > hi
+}
+
+// A negative constant is stored as its two's complement, so it fits if it does when read back
+// as signed, even though every integer is unsigned at runtime
+fn fits(val: i128, size: usize) -> bool {
+    let Some(bits) = size.checked_mul(8).filter(|bits| *bits < 128)
+    else {
+        return true;
+    };
+    (-(1 << (bits - 1))..(1 << bits)).contains(&val)
+}
@@ -0,0 +1,1397 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+
+use crate::ast::{ASTKind, DestructureBinding, LabelAttribute, MatchArm, Operator, Parameter,
+                 Program, Type, AST, POINTER_SIZE, RETURN_VALUES};
+use crate::report::{LogHandler, Report, ReportKind};
+use crate::stack::Frame;
+
+// `;rN` bindings live in these, they're all callee saved so calls leave them be
+const BINDABLE_REGISTERS: [&str; 5] = ["rbx", "r12", "r13", "r14", "r15"];
+const CALL_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+const SYSCALL_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "r10", "r8", "r9"];
+// each element of a heap returned by value comes back in one of these
+const RETURN_REGISTERS: [&str; RETURN_VALUES] = ["rax", "rdx"];
+
+// a match with this many patterns or more jumps through a table when it isn't too sparse
+const JUMP_TABLE_CASES: usize = 4;
+// at most this many table entries per pattern
+const JUMP_TABLE_SPREAD: usize = 2;
+
+// a generic function calling itself with ever larger types would never stop being instantiated
+const MAX_INSTANCES: usize = 64;
+
+// the only architecture code can be generated for
+pub const ARCH: &str = "x86_64";
+
+// x86_64 linux
+const SYSCALLS: [(&str, usize); 12] = [
+    ("read", 0),
+    ("write", 1),
+    ("open", 2),
+    ("close", 3),
+    ("mmap", 9),
+    ("munmap", 11),
+    ("brk", 12),
+    ("getpid", 39),
+    ("fork", 57),
+    ("execve", 59),
+    ("exit", 60),
+    ("exit_group", 231),
+];
+
+#[derive(Clone, Copy)]
+enum Location {
+    // offset below rbp
+    Stack(usize),
+    Register(&'static str),
+}
+
+struct Local {
+    location: Location,
+    ty:       Type,
+}
+
+pub struct Generator {
+    filename: &'static str,
+    handler:  LogHandler,
+
+    text:       String,
+    data:       String,
+    // everything besides the entry point
+    routines:   String,
+    scopes:     Vec<HashMap<String, Local>>,
+    frame_size: usize,
+    strings:    usize,
+    // conditionals and loops so far, for naming their labels
+    branches:   usize,
+    // the loops around the code being generated, by name and label, innermost last
+    loops:      Vec<(Option<String>, String)>,
+
+    // symbols from `extern` declarations, with their return types
+    externs: Vec<(String, Option<Type>)>,
+    // `export`ed symbols, besides _start
+    globals: Vec<String>,
+    // the label or function marked `entry`, and whether it's a function
+    entry:   Option<(String, bool)>,
+    // section the code being generated goes in, None for .text
+    section: Option<String>,
+
+    // bodies of `inline` functions, expanded at every call
+    inlines:  HashMap<String, (Vec<Parameter>, AST)>,
+    // functions being expanded right now, recursive calls to them aren't inlined
+    inlining: Vec<String>,
+    inlined:  usize,
+    // field types of every struct, in order
+    structs:  HashMap<String, Vec<(String, Type)>>,
+
+    // generic functions by name, each instance is generated the first time it's called
+    generics:     HashMap<String, AST>,
+    // the types each generic function has been instantiated with so far, in order
+    instances:    HashMap<String, Vec<Vec<Type>>>,
+    // what the type parameters of the routine being generated stand for
+    substitution: Vec<(String, Type)>,
+
+    // stack accounting for the routine being generated
+    frames:          Vec<Frame>,
+    calls:           Vec<String>,
+    bound_registers: Vec<&'static str>,
+    depth:           usize,
+    max_depth:       usize,
+    // whose `.end` a `ret` jumps to, None while generating the entry point
+    routine:         Option<String>,
+    // temporaries pushed before the inline expansion being generated, which its `ret` keeps
+    inline_depth:    usize,
+}
+
+impl Generator {
+    pub fn new(filename: &'static str, handler: LogHandler) -> Self {
+        Self {
+            filename,
+            handler,
+            text: String::new(),
+            data: String::new(),
+            routines: String::new(),
+            structs: HashMap::new(),
+            externs: Vec::new(),
+            globals: Vec::new(),
+            entry: None,
+            section: None,
+            inlines: HashMap::new(),
+            inlining: Vec::new(),
+            inlined: 0,
+            generics: HashMap::new(),
+            instances: HashMap::new(),
+            substitution: Vec::new(),
+            scopes: Vec::new(),
+            frame_size: 0,
+            strings: 0,
+            branches: 0,
+            loops: Vec::new(),
+            frames: Vec::new(),
+            calls: Vec::new(),
+            bound_registers: Vec::new(),
+            depth: 0,
+            max_depth: 0,
+            routine: None,
+            inline_depth: 0,
+        }
+    }
+
+    fn report(&self, report: Report) {
+        let (priority, log) = report.into();
+        self.handler.add_log(priority, log);
+    }
+
+    fn emit<T: Display>(&mut self, instruction: T) {
+        writeln!(self.text, "    {instruction}").unwrap();
+    }
+
+    fn push(&mut self, register: &str) {
+        self.emit(format!("push {register}"));
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn pop(&mut self, register: &str) {
+        self.emit(format!("pop {register}"));
+        self.depth -= 1;
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    // Closes off the current routine's stack frame.
+    // Entry points are jumped to, so they have no return address or registers to save.
+    fn end_frame(&mut self, name: String, is_entry: bool) -> usize {
+        let saved = match is_entry {
+            true => 0,
+            false => 2 * POINTER_SIZE + self.bound_registers.len() * POINTER_SIZE,
+        };
+
+        let locals = std::mem::take(&mut self.frame_size).next_multiple_of(16);
+        self.frames.push(Frame {
+            name,
+            locals,
+            spills: std::mem::take(&mut self.max_depth) * POINTER_SIZE,
+            saved,
+            calls: std::mem::take(&mut self.calls),
+        });
+        self.bound_registers.clear();
+        self.depth = 0;
+        locals
+    }
+
+    fn unsupported(&self, ast: &AST, what: &str) {
+        self.report(
+            ReportKind::UnsupportedExpression
+                .title(format!("Code generation for {what} isn't supported yet"))
+                .span(ast.span),
+        );
+    }
+
+    pub fn generate(&mut self, program: &Program) -> String {
+        // inline and generic functions can be called before they're defined
+        for stmt in &program.stmts {
+            let ASTKind::FunctionDefinition(name, generics, attrs, params, _, body) = &stmt.kind
+            else {
+                continue;
+            };
+
+            if !generics.is_empty() {
+                self.generics.insert(name.clone(), stmt.clone());
+            }
+            else if attrs.contains(&LabelAttribute::Inline) {
+                self.inlines.insert(name.clone(), (params.clone(), (**body).clone()));
+            }
+        }
+
+        self.scopes.push(HashMap::new());
+        program.stmts.iter().for_each(|stmt| self.gen_statement(stmt));
+        self.scopes.pop();
+
+        // falling off the end of the program runs the entry function, or exits cleanly
+        match self.entry.clone() {
+            Some((name, true)) => {
+                self.emit(format!("call {name}"));
+                self.calls.push(name);
+            },
+            _ => self.emit("xor eax, eax"),
+        }
+        self.gen_exit();
+        if self.section.take().is_some() {
+            self.text.push_str("section .text\n");
+        }
+        let locals = self.end_frame(String::from("_start"), true);
+
+        let mut out = format!("; generated by sharc from {}\n\n", self.filename);
+        if !self.data.is_empty() {
+            writeln!(out, "section .data\n{}", self.data).unwrap();
+        }
+
+        self.externs.iter().for_each(|(name, _)| writeln!(out, "extern {name}").unwrap());
+        if !self.externs.is_empty() {
+            out.push('\n');
+        }
+
+        out.push_str("section .text\nglobal _start\n");
+        self.globals.iter().for_each(|name| writeln!(out, "global {name}").unwrap());
+        out.push_str("_start:\n    mov rbp, rsp\n");
+        if locals > 0 {
+            writeln!(out, "    sub rsp, {locals}").unwrap();
+        }
+        if let Some((name, false)) = &self.entry {
+            writeln!(out, "    jmp {name}").unwrap();
+        }
+        out.push_str(&self.text);
+        out.push_str(&self.routines);
+        out
+    }
+
+    // Generates a routine following the System V calling convention.
+    // It gets a frame of its own, so whatever is being generated is put aside until it's done.
+    fn gen_routine(
+        &mut self, ast: &AST, label: &str, attrs: &[LabelAttribute], params: &[Parameter],
+        body: &AST,
+    ) {
+        if params.len() > CALL_REGISTERS.len() {
+            return self
+                .unsupported(ast, &format!("more than {} parameters", CALL_REGISTERS.len()));
+        }
+
+        let text = std::mem::take(&mut self.text);
+        let scopes = std::mem::replace(&mut self.scopes, vec![HashMap::new()]);
+        let frame_size = std::mem::take(&mut self.frame_size);
+        let calls = std::mem::take(&mut self.calls);
+        let bound_registers = std::mem::take(&mut self.bound_registers);
+        let (depth, max_depth) =
+            (std::mem::take(&mut self.depth), std::mem::take(&mut self.max_depth));
+        let routine = self.routine.replace(label.to_string());
+        let outer_section = self.section.take();
+        let loops = std::mem::take(&mut self.loops);
+
+        for (param, register) in params.iter().zip(CALL_REGISTERS) {
+            if self.bind(ast, &param.name, &param.local_type()) {
+                self.store_local(&param.name, register);
+            }
+        }
+        self.gen_statement(body);
+
+        let saved = self.bound_registers.clone();
+        let locals = self.end_frame(label.to_string(), false);
+
+        let section = section(attrs);
+        if let Some(section) = section {
+            writeln!(self.routines, "\nsection {section}").unwrap();
+        }
+        let header = self.label_header(label, attrs, true);
+        writeln!(self.routines, "\n{header}").unwrap();
+        self.routines.push_str("    push rbp\n    mov rbp, rsp\n");
+        if locals > 0 {
+            writeln!(self.routines, "    sub rsp, {locals}").unwrap();
+        }
+        saved.iter().for_each(|register| writeln!(self.routines, "    push {register}").unwrap());
+        self.routines.push_str(&std::mem::replace(&mut self.text, text));
+
+        // nothing returns from a noreturn function, so falling off its end is a bug
+        if attrs.contains(&LabelAttribute::NoReturn) {
+            self.routines.push_str("    ud2\n");
+        }
+        else {
+            writeln!(self.routines, "{label}.end:").unwrap();
+            // a `ret` within an expression gets here with temporaries still pushed
+            if !saved.is_empty() {
+                let depth = locals + saved.len() * POINTER_SIZE;
+                writeln!(self.routines, "    lea rsp, [rbp - {depth}]").unwrap();
+            }
+            saved.iter().rev().for_each(|register| {
+                writeln!(self.routines, "    pop {register}").unwrap();
+            });
+            self.routines.push_str("    leave\n    ret\n");
+        }
+        if std::mem::replace(&mut self.section, outer_section).is_some() || section.is_some() {
+            self.routines.push_str("section .text\n");
+        }
+
+        self.scopes = scopes;
+        self.frame_size = frame_size;
+        self.calls = calls;
+        self.bound_registers = bound_registers;
+        (self.depth, self.max_depth) = (depth, max_depth);
+        self.routine = routine;
+        self.loops = loops;
+    }
+
+    // Jumps to `target` unless `cond` holds.
+    // Comparisons branch on the flags directly rather than materialising a boolean first.
+    fn gen_branch(&mut self, cond: &AST, target: &str) {
+        let is_mutating = |side: &AST| {
+            matches!(side.kind, ASTKind::UnaryExpr(Operator::MutatePre | Operator::MutatePost, _))
+        };
+
+        match &cond.kind {
+            ASTKind::BinaryExpr(op, lhs, rhs) if !is_mutating(lhs) && !is_mutating(rhs) => {
+                let jump = match op {
+                    Operator::Eq => "jne",
+                    Operator::Neq => "je",
+                    Operator::Lt => "jae",
+                    Operator::Le => "ja",
+                    Operator::Gt => "jbe",
+                    Operator::Ge => "jb",
+                    _ => return self.gen_branch_on_value(cond, target),
+                };
+
+                self.gen_expression(lhs);
+                self.push("rax");
+                self.gen_expression(rhs);
+                self.emit("mov rcx, rax");
+                self.pop("rax");
+                self.emit("cmp rax, rcx");
+                self.emit(format!("{jump} {target}"));
+            },
+            ASTKind::TypeAnnotation(_, inner) => self.gen_branch(inner, target),
+            _ => self.gen_branch_on_value(cond, target),
+        }
+    }
+
+    fn gen_branch_on_value(&mut self, cond: &AST, target: &str) {
+        self.gen_expression(cond);
+        self.emit("test rax, rax");
+        self.emit(format!("jz {target}"));
+    }
+
+    // Arms are labelled in order, whatever matches none of them goes to the last arm of an
+    // exhaustive match or straight past the end otherwise.
+    fn gen_match(&mut self, value: &AST, arms: &[MatchArm], is_exhaustive: bool) {
+        self.branches += 1;
+        let label = format!("match.{}", self.branches);
+        let default = match is_exhaustive {
+            true => format!("{label}.arm.{}", arms.len() - 1),
+            false => format!("{label}.end"),
+        };
+
+        self.gen_expression(value);
+        if !self.gen_jump_table(&label, arms, &default) {
+            // the default already goes to the last arm of an exhaustive match
+            let tested = if is_exhaustive { arms.len() - 1 } else { arms.len() };
+            for (i, arm) in arms.iter().enumerate().take(tested) {
+                for &(lo, hi) in &arm.ranges {
+                    match (lo, hi) {
+                        (lo, hi) if lo == hi => {
+                            self.emit_immediate("cmp", "rax", lo);
+                            self.emit(format!("je {label}.arm.{i}"));
+                        },
+                        (0, hi) => {
+                            self.emit_immediate("cmp", "rax", hi);
+                            self.emit(format!("jbe {label}.arm.{i}"));
+                        },
+                        // one unsigned compare covers both ends
+                        (lo, hi) => {
+                            self.emit("mov rcx, rax");
+                            self.emit_immediate("sub", "rcx", lo);
+                            self.emit_immediate("cmp", "rcx", hi - lo);
+                            self.emit(format!("jbe {label}.arm.{i}"));
+                        },
+                    }
+                }
+            }
+            self.emit(format!("jmp {default}"));
+        }
+
+        for (i, arm) in arms.iter().enumerate() {
+            writeln!(self.text, "{label}.arm.{i}:").unwrap();
+            self.gen_statement(&arm.body);
+            if i != arms.len() - 1 {
+                self.emit(format!("jmp {label}.end"));
+            }
+        }
+        writeln!(self.text, "{label}.end:").unwrap();
+    }
+
+    // Jumps through a table indexed by the value in rax, false if the arms are too few or sparse.
+    fn gen_jump_table(&mut self, label: &str, arms: &[MatchArm], default: &str) -> bool {
+        let ranges = arms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, arm)| arm.ranges.iter().map(move |&range| (i, range)))
+            .collect::<Vec<_>>();
+
+        let (Some(min), Some(max)) = (
+            ranges.iter().map(|(_, (lo, _))| *lo).min(),
+            ranges.iter().map(|(_, (_, hi))| *hi).max(),
+        )
+        else {
+            return false;
+        };
+
+        // patterns may reach up to u64::MAX, so the span itself can overflow
+        let Some(entries) = (max - min)
+            .checked_add(1)
+            .filter(|entries| *entries <= ranges.len().saturating_mul(JUMP_TABLE_SPREAD))
+        else {
+            return false;
+        };
+        if ranges.len() < JUMP_TABLE_CASES {
+            return false;
+        }
+
+        // earlier arms win where patterns overlap
+        let mut table = vec![None; entries];
+        for (i, (lo, hi)) in ranges {
+            table[lo - min..=hi - min]
+                .iter_mut()
+                .filter(|entry| entry.is_none())
+                .for_each(|entry| *entry = Some(format!("{label}.arm.{i}")));
+        }
+        let table = table
+            .into_iter()
+            .map(|entry| entry.unwrap_or_else(|| default.to_string()))
+            .collect::<Vec<_>>();
+        writeln!(self.data, "    {label}.table: dq {}", table.join(", ")).unwrap();
+
+        self.emit("mov rcx, rax");
+        if min != 0 {
+            self.emit_immediate("sub", "rcx", min);
+        }
+        self.emit_immediate("cmp", "rcx", entries - 1);
+        self.emit(format!("ja {default}"));
+        self.emit(format!("jmp [{label}.table + rcx * 8]"));
+        true
+    }
+
+    // Instructions only take 32 bit immediates, anything larger goes through rdx
+    fn emit_immediate(&mut self, op: &str, register: &str, value: usize) {
+        match i32::try_from(value) {
+            Ok(value) => self.emit(format!("{op} {register}, {value}")),
+            Err(_) => {
+                self.emit(format!("mov rdx, {value}"));
+                self.emit(format!("{op} {register}, rdx"));
+            },
+        }
+    }
+
+    // Expands a call to an `inline` function in place, a `ret` jumps to the end of the expansion.
+    // `substitution` is what the type parameters stand for if it's an instance of a generic one.
+    fn gen_inline(
+        &mut self, ast: &AST, name: &str, params: &[Parameter], body: &AST, args: &[AST],
+        substitution: Vec<(String, Type)>,
+    ) {
+        args.iter().for_each(|arg| {
+            self.gen_expression(arg);
+            self.push("rax");
+        });
+        let outer = std::mem::replace(&mut self.substitution, substitution);
+
+        self.scopes.push(HashMap::new());
+        for param in params.iter().rev() {
+            // the caller may already have its own bindings in the parameter's register
+            let ty = match param.local_type() {
+                Type::Register { inner, .. } => inner.map_or(Type::Size(POINTER_SIZE), |ty| *ty),
+                ty => ty,
+            };
+
+            self.pop("rax");
+            if self.bind(ast, &param.name, &ty) {
+                self.store_local(&param.name, "rax");
+            }
+        }
+
+        self.inlined += 1;
+        let label = format!("{name}.inline.{}", self.inlined);
+        let routine = self.routine.replace(label.clone());
+        let inline_depth = std::mem::replace(&mut self.inline_depth, self.depth);
+        self.inlining.push(name.to_string());
+        self.gen_statement(body);
+        self.inlining.pop();
+        self.inline_depth = inline_depth;
+        self.routine = routine;
+        self.scopes.pop();
+        self.substitution = outer;
+
+        writeln!(self.text, "{label}.end:").unwrap();
+    }
+
+    // Everything that goes before a label besides its section, records where it's visible from
+    fn label_header(&mut self, name: &str, attrs: &[LabelAttribute], is_function: bool) -> String {
+        let mut header = String::new();
+        for attr in attrs {
+            match attr {
+                LabelAttribute::Align(align) => writeln!(header, "align {align}").unwrap(),
+                LabelAttribute::Export => self.globals.push(name.to_string()),
+                LabelAttribute::Entry => self.entry = Some((name.to_string(), is_function)),
+                // handled by the caller
+                LabelAttribute::Inline | LabelAttribute::NoReturn | LabelAttribute::Section(_) => {
+                },
+            }
+        }
+        header.push_str(name);
+        header.push(':');
+        header
+    }
+
+    // Reserves a stack slot in the current frame, returning its offset below rbp
+    fn allocate(&mut self, size: usize) -> usize {
+        self.frame_size = (self.frame_size + size).next_multiple_of(size.min(POINTER_SIZE));
+        self.frame_size
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn gen_statement(&mut self, ast: &AST) {
+        match &ast.kind {
+            ASTKind::VariableDefinition(name, ty, _, value) => {
+                if !self.bind(ast, name, ty) {
+                    return;
+                }
+
+                match value {
+                    Some(value) if self.is_returned(value) => {
+                        self.gen_expression(value);
+                        self.store_values(&ASTKind::Identifier(name.clone()).into_ast(ast.span));
+                    },
+                    Some(value) => {
+                        self.gen_expression(value);
+                        self.store_local(name, "rax");
+                    },
+                    None => {},
+                }
+            },
+
+            ASTKind::Destructure(bindings, value) => self.gen_destructure(ast, bindings, value),
+
+            ASTKind::StructDefinition(name, fields) => {
+                let fields =
+                    fields.iter().map(|field| (field.name.clone(), field.ty.clone())).collect();
+                self.structs.insert(name.clone(), fields);
+            },
+
+            ASTKind::ExternDeclaration(name, _, ret) =>
+                self.externs.push((name.clone(), ret.clone())),
+
+            ASTKind::OperatorDefinition(label, _, params, _, body) =>
+                self.gen_routine(ast, label, &[], params, body),
+            ASTKind::FunctionDefinition(name, generics, attrs, params, _, body)
+                if generics.is_empty() =>
+                self.gen_routine(ast, name, attrs, params, body),
+
+            // only matter to the checker, and to the driver through `Program::metadata`.
+            // Generic functions are generated once per instance, as they're called.
+            ASTKind::EnumDefinition(..)
+            | ASTKind::TypeAlias(..)
+            | ASTKind::Tag(..)
+            | ASTKind::FunctionDefinition(..) => {},
+
+            ASTKind::LabelDefinition(Some(name), attrs) => {
+                let section = section(attrs).map(ToString::to_string);
+                if section != self.section {
+                    writeln!(self.text, "section {}", section.as_deref().unwrap_or(".text"))
+                        .unwrap();
+                    self.section = section;
+                }
+                let header = self.label_header(name, attrs, false);
+                writeln!(self.text, "{header}").unwrap();
+            },
+
+            ASTKind::Block(stmts) => {
+                self.scopes.push(HashMap::new());
+                stmts.iter().for_each(|stmt| self.gen_statement(stmt));
+                self.scopes.pop();
+            },
+
+            ASTKind::Return(value) => {
+                match value {
+                    Some(value) => match &value.kind {
+                        ASTKind::HeapLiteral(values) => {
+                            self.gen_arguments(value, values, &RETURN_REGISTERS);
+                        },
+                        _ if !self.is_returned(value)
+                            && self
+                                .type_of(value)
+                                .is_some_and(|ty| ty.return_values().is_some()) =>
+                            self.load_values(value),
+                        _ => self.gen_expression(value),
+                    },
+                    None => self.emit("xor eax, eax"),
+                }
+                // a routine resets rsp at its end, an expansion has no frame of its own to do so
+                if !self.inlining.is_empty() && self.depth > self.inline_depth {
+                    self.emit(format!(
+                        "add rsp, {}",
+                        (self.depth - self.inline_depth) * POINTER_SIZE
+                    ));
+                }
+                match &self.routine {
+                    Some(label) => self.emit(format!("jmp {label}.end")),
+                    None => self.gen_exit(),
+                }
+            },
+
+            // `continue` jumps back to the condition
+            ASTKind::Loop(name, cond, body) => {
+                self.branches += 1;
+                let label = format!("loop.{}", self.branches);
+
+                writeln!(self.text, "{label}:").unwrap();
+                if let Some(cond) = cond {
+                    self.gen_branch(cond, &format!("{label}.end"));
+                }
+
+                self.loops.push((name.clone(), label.clone()));
+                self.gen_statement(body);
+                self.loops.pop();
+
+                self.emit(format!("jmp {label}"));
+                writeln!(self.text, "{label}.end:").unwrap();
+            },
+            ASTKind::Break(name) =>
+                if let Some(label) = self.loop_label(name.as_deref()) {
+                    self.emit(format!("jmp {label}.end"));
+                },
+            ASTKind::Continue(name) =>
+                if let Some(label) = self.loop_label(name.as_deref()) {
+                    self.emit(format!("jmp {label}"));
+                },
+
+            _ => self.gen_expression(ast),
+        }
+    }
+
+    // The innermost loop called `name`, or just the innermost without one
+    fn loop_label(&self, name: Option<&str>) -> Option<String> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|(loop_name, _)| name.is_none() || loop_name.as_deref() == name)
+            .map(|(_, label)| label.clone())
+    }
+
+    // Gives `name` somewhere to live in the current scope, false if that isn't possible.
+    fn bind(&mut self, ast: &AST, name: &str, ty: &Type) -> bool {
+        let ty = &ty.substitute(&self.substitution);
+        let location = match ty.expanded() {
+            Type::Register { ident, .. } => match BINDABLE_REGISTERS.get(*ident) {
+                Some(register) => {
+                    if !self.bound_registers.contains(register) {
+                        self.bound_registers.push(register);
+                    }
+                    Location::Register(register)
+                },
+                None => {
+                    self.report(
+                        ReportKind::InvalidRegister
+                            .title(format!("Register r{ident} can't be bound"))
+                            .span(ast.span)
+                            .note(format!(
+                                "HINT: Only r0 through r{} are available",
+                                BINDABLE_REGISTERS.len() - 1
+                            )),
+                    );
+                    return false;
+                },
+            },
+            ty => Location::Stack(self.allocate(ty.size().unwrap_or(POINTER_SIZE))),
+        };
+
+        self.scopes
+            .last_mut()
+            .expect("Generator has no scope")
+            .insert(name.to_string(), Local { location, ty: ty.clone() });
+        true
+    }
+
+    fn gen_destructure(&mut self, ast: &AST, bindings: &[DestructureBinding], value: &AST) {
+        let default = Type::Size(POINTER_SIZE);
+        let types = bindings.iter().map(|binding| binding.ty.as_ref().unwrap_or(&default));
+
+        if self.is_returned(value) {
+            self.gen_expression(value);
+            for ((binding, ty), register) in bindings.iter().zip(types).zip(RETURN_REGISTERS) {
+                if !self.bind(ast, &binding.name, ty) {
+                    return;
+                }
+                self.store_local(&binding.name, register);
+            }
+            return;
+        }
+
+        if let ASTKind::HeapLiteral(values) = &value.kind {
+            for ((binding, ty), value) in bindings.iter().zip(types).zip(values) {
+                if !self.bind(ast, &binding.name, ty) {
+                    return;
+                }
+                self.gen_expression(value);
+                self.store_local(&binding.name, "rax");
+            }
+            return;
+        }
+
+        let Some((through_pointer, elements)) = self.type_of(value).and_then(|ty| self.layout(&ty))
+        else {
+            return self.unsupported(value, "destructuring this");
+        };
+
+        // the address of the first element stays on the stack throughout
+        match through_pointer {
+            true => self.gen_expression(value),
+            false => self.gen_address(value),
+        }
+        self.push("rax");
+
+        let mut offset = 0;
+        for ((binding, ty), element) in bindings.iter().zip(types).zip(elements) {
+            let size = element.size().unwrap_or(POINTER_SIZE);
+            if size > POINTER_SIZE {
+                self.unsupported(ast, "destructuring into a struct or heap");
+                break;
+            }
+            if !self.bind(ast, &binding.name, ty) {
+                break;
+            }
+
+            self.emit("mov rcx, [rsp]");
+            self.load_memory(&format!("rcx + {offset}"), size);
+            self.store_local(&binding.name, "rax");
+            offset += size;
+        }
+        self.pop("rcx");
+    }
+
+    // Whether `ast` is a call to a function returning several values, they're left in the return
+    // registers rather than just rax
+    fn is_returned(&self, ast: &AST) -> bool {
+        matches!(&ast.kind, ASTKind::TypeAnnotation(ty, call)
+            if matches!(call.kind, ASTKind::Call(..))
+                && ty.substitute(&self.substitution).return_values().is_some())
+    }
+
+    // Stores what a call left in the return registers into the elements of `place`
+    fn store_values(&mut self, place: &AST) {
+        let Some(values) =
+            self.type_of(place).and_then(|ty| ty.return_values().map(<[Type]>::to_vec))
+        else {
+            return self.unsupported(place, "storing several values into this");
+        };
+
+        RETURN_REGISTERS[..values.len()].iter().for_each(|register| self.push(register));
+        self.gen_address(place);
+        self.emit("mov rcx, rax");
+        let sizes =
+            values.iter().map(|value| value.size().unwrap_or(POINTER_SIZE)).collect::<Vec<_>>();
+        for (i, size) in sizes.iter().enumerate().rev() {
+            let offset = sizes[..i].iter().sum::<usize>();
+            self.pop("rax");
+            self.emit(format!("mov {} [rcx + {offset}], {}", width(*size), sized("rax", *size)));
+        }
+    }
+
+    // Loads the elements of a heap in memory into the return registers
+    fn load_values(&mut self, place: &AST) {
+        let Some(values) =
+            self.type_of(place).and_then(|ty| ty.return_values().map(<[Type]>::to_vec))
+        else {
+            return;
+        };
+
+        self.gen_address(place);
+        self.emit("mov rcx, rax");
+        let sizes =
+            values.iter().map(|value| value.size().unwrap_or(POINTER_SIZE)).collect::<Vec<_>>();
+        // the first element goes last, the others pass through rax on their way
+        for (i, (size, register)) in sizes.iter().zip(RETURN_REGISTERS).enumerate().rev() {
+            let offset = sizes[..i].iter().sum::<usize>();
+            self.load_memory(&format!("rcx + {offset}"), *size);
+            if register != "rax" {
+                self.emit(format!("mov {register}, rax"));
+            }
+        }
+    }
+
+    // The elements of a heap or struct, and whether they're reached through a pointer
+    fn layout(&self, ty: &Type) -> Option<(bool, Vec<Type>)> {
+        match ty.expanded() {
+            Type::Heap { is_pointer, contents } => Some((*is_pointer, contents.clone())),
+            Type::Struct(name, _) => self
+                .structs
+                .get(name)
+                .map(|fields| (false, fields.iter().map(|(_, ty)| ty.clone()).collect())),
+            Type::Register { inner: Some(inner), .. } => self.layout(inner),
+            _ => None,
+        }
+    }
+
+    // The offset and type of `field` within a struct or heap, and whether it's reached through
+    // a pointer, the checker has already made sure it exists
+    fn field(&self, ty: &Type, field: &AST) -> Option<(usize, Type, bool)> {
+        let (through_pointer, ty) = match ty.expanded() {
+            Type::Register { inner: Some(inner), .. } => return self.field(inner, field),
+            Type::Heap { is_pointer: true, .. } => (true, ty.pointee()?),
+            ty => (false, ty.clone()),
+        };
+        let fields: Vec<(Option<&str>, Type)> = match ty.expanded() {
+            Type::Struct(name, _) => self
+                .structs
+                .get(name)?
+                .iter()
+                .map(|(name, ty)| (Some(name.as_str()), ty.clone()))
+                .collect(),
+            Type::Heap { is_pointer: false, contents } =>
+                contents.iter().map(|ty| (None, ty.clone())).collect(),
+            _ => return None,
+        };
+
+        let position = match &field.kind {
+            ASTKind::IntegerLiteral(position) => *position,
+            ASTKind::Identifier(name) =>
+                fields.iter().position(|(field, _)| *field == Some(name))?,
+            _ => return None,
+        };
+        let (_, ty) = fields.get(position)?;
+        let offset =
+            fields[..position].iter().map(|(_, ty)| ty.size().unwrap_or(POINTER_SIZE)).sum();
+        Some((offset, ty.clone(), through_pointer))
+    }
+
+    // Exits the program with the status in rax
+    fn gen_exit(&mut self) {
+        self.emit("mov rdi, rax");
+        self.emit("mov rax, 60");
+        self.emit("syscall");
+    }
+
+    // Leaves the result in rax
+    fn gen_expression(&mut self, ast: &AST) {
+        match &ast.kind {
+            ASTKind::IntegerLiteral(val) => self.emit(format!("mov rax, {val}")),
+            ASTKind::CharLiteral(val) => self.emit(format!("mov rax, {}", *val as u32)),
+            ASTKind::SizeOf(ty) => match ty.substitute(&self.substitution).size() {
+                Some(size) => self.emit(format!("mov rax, {size}")),
+                None => self.unsupported(ast, "sizes of unsized types"),
+            },
+            ASTKind::StringLiteral(val) => {
+                let label = format!("str{}", self.strings);
+                self.strings += 1;
+
+                let bytes = val.bytes().map(|b| b.to_string()).collect::<Vec<_>>().join(", ");
+                writeln!(self.data, "    {label}: db {bytes}").unwrap();
+                self.emit(format!("lea rax, [rel {label}]"));
+            },
+            ASTKind::Identifier(name) => match self.lookup(name) {
+                Some(Local { location, ty }) => {
+                    let (location, size) = (*location, ty.size().unwrap_or(POINTER_SIZE));
+                    self.load_local(location, size);
+                },
+                // not a binding, so it's the address of a label
+                None => self.emit(format!("lea rax, [rel {name}]")),
+            },
+
+            ASTKind::Cast(ty, value, is_explicit) => {
+                let from = self.type_of(value).and_then(|ty| ty.size());
+                self.gen_expression(value);
+
+                // arithmetic may have left junk above the source's width, so redo that first,
+                // `cast` spreads the sign bit while `->` doesn't
+                match from {
+                    Some(from @ (1 | 2 | 4)) if *is_explicit => self.sign_extend(from),
+                    Some(from @ (1 | 2 | 4)) => self.extend(from),
+                    _ => {},
+                }
+                if let Some(to @ (1 | 2 | 4)) = ty.substitute(&self.substitution).size() {
+                    self.extend(to);
+                }
+            },
+
+            ASTKind::TypeAnnotation(_, inner)
+            | ASTKind::UnaryExpr(
+                Operator::MutatePre | Operator::MutatePost | Operator::Positive,
+                inner,
+            ) => self.gen_expression(inner),
+
+            ASTKind::Match(value, arms, is_exhaustive) =>
+                self.gen_match(value, arms, *is_exhaustive),
+            ASTKind::Conditional(cond, then, otherwise) => {
+                self.branches += 1;
+                let label = format!("if.{}", self.branches);
+
+                let skip = if otherwise.is_some() { "else" } else { "end" };
+                self.gen_branch(cond, &format!("{label}.{skip}"));
+                self.gen_statement(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.emit(format!("jmp {label}.end"));
+                    writeln!(self.text, "{label}.else:").unwrap();
+                    self.gen_statement(otherwise);
+                }
+                writeln!(self.text, "{label}.end:").unwrap();
+            },
+
+            // the left side is evaluated first and its value dropped
+            ASTKind::BinaryExpr(Operator::Sequence, lhs, rhs) => {
+                self.gen_expression(lhs);
+                self.gen_expression(rhs);
+            },
+
+            ASTKind::BinaryExpr(Operator::Index | Operator::Access, ..) => {
+                let size = self
+                    .type_of(ast)
+                    .and_then(|ty| ty.size())
+                    .filter(|size| *size <= POINTER_SIZE)
+                    .unwrap_or(POINTER_SIZE);
+                self.gen_address(ast);
+                self.load_memory("rax", size);
+            },
+            ASTKind::BinaryExpr(Operator::Assign, target, value) => {
+                self.gen_expression(value);
+                match self.is_returned(value) {
+                    true => self.store_values(target),
+                    false => self.store_place(target),
+                }
+            },
+
+            ASTKind::BinaryExpr(op, lhs, rhs) => {
+                self.gen_expression(lhs);
+                self.push("rax");
+                self.gen_expression(rhs);
+                self.emit("mov rcx, rax");
+                self.pop("rax");
+
+                match op {
+                    Operator::Add => self.emit("add rax, rcx"),
+                    Operator::Substract => self.emit("sub rax, rcx"),
+                    Operator::Multiply => self.emit("imul rax, rcx"),
+                    Operator::Divide | Operator::Modulo => {
+                        self.emit("xor edx, edx");
+                        self.emit("div rcx");
+                        if *op == Operator::Modulo {
+                            self.emit("mov rax, rdx");
+                        }
+                    },
+                    Operator::ShiftL => self.emit("shl rax, cl"),
+                    Operator::ShiftR => self.emit("shr rax, cl"),
+                    Operator::And => self.emit("and rax, rcx"),
+                    Operator::Or => self.emit("or rax, rcx"),
+                    Operator::Xor => self.emit("xor rax, rcx"),
+                    Operator::Eq
+                    | Operator::Neq
+                    | Operator::Lt
+                    | Operator::Le
+                    | Operator::Gt
+                    | Operator::Ge => {
+                        let set = match op {
+                            Operator::Eq => "sete",
+                            Operator::Neq => "setne",
+                            Operator::Lt => "setb",
+                            Operator::Le => "setbe",
+                            Operator::Gt => "seta",
+                            _ => "setae",
+                        };
+                        self.emit("cmp rax, rcx");
+                        self.emit(format!("{set} al"));
+                        self.emit("movzx eax, al");
+                    },
+                    op => return self.unsupported(ast, &format!("{op:?}")),
+                }
+
+                // `x' + 1` writes the result back into x
+                for side in [lhs, rhs] {
+                    if let ASTKind::UnaryExpr(Operator::MutatePre | Operator::MutatePost, target) =
+                        &side.kind
+                    {
+                        self.store_place(target);
+                    }
+                }
+            },
+
+            // yields the value from before the change
+            ASTKind::UnaryExpr(op @ (Operator::Increment | Operator::Decrement), target) => {
+                self.gen_expression(target);
+                self.push("rax");
+                self.emit(if *op == Operator::Increment { "inc rax" } else { "dec rax" });
+                self.store_place(target);
+                self.pop("rax");
+            },
+            ASTKind::UnaryExpr(Operator::Negative, operand) => {
+                self.gen_expression(operand);
+                self.emit("neg rax");
+            },
+            ASTKind::UnaryExpr(Operator::Not, operand) => {
+                self.gen_expression(operand);
+                self.emit("not rax");
+            },
+            ASTKind::UnaryExpr(Operator::Deref, inner) => {
+                let size = self.pointee_size(inner);
+                self.gen_expression(inner);
+                self.load_memory("rax", size);
+            },
+            ASTKind::UnaryExpr(Operator::AddressOf, inner) => self.gen_address(inner),
+
+            ASTKind::Call(callee, args, true) => {
+                let ASTKind::Identifier(name) = &callee.kind
+                else {
+                    return self.unsupported(callee, "calls through an expression");
+                };
+
+                if self.gen_arguments(ast, args, &CALL_REGISTERS) {
+                    self.gen_external_call(name);
+                    // there's no frame for it, so the stack usage ends up a lower bound
+                    if !self.calls.contains(name) {
+                        self.calls.push(name.clone());
+                    }
+                }
+            },
+            ASTKind::Call(callee, args, false) => {
+                let (name, substitution) = match &callee.kind {
+                    ASTKind::Identifier(name) => (name.clone(), Vec::new()),
+                    ASTKind::Instance(name, types) => match self.instantiate(callee, name, types) {
+                        Some(instance) => instance,
+                        None => return,
+                    },
+                    _ => return self.unsupported(callee, "calls through an expression"),
+                };
+
+                if let Some((params, body)) =
+                    self.inlines.get(&name).filter(|_| !self.inlining.contains(&name)).cloned()
+                {
+                    return self.gen_inline(ast, &name, &params, &body, args, substitution);
+                }
+
+                if self.gen_arguments(ast, args, &CALL_REGISTERS) {
+                    self.emit(format!("call {name}"));
+                    if !self.calls.contains(&name) {
+                        self.calls.push(name);
+                    }
+                }
+            },
+            ASTKind::Syscall(name, args) => {
+                let Some((_, number)) = SYSCALLS.iter().find(|(n, _)| n == name)
+                else {
+                    return self.report(
+                        ReportKind::UnknownSyscall
+                            .title(format!("Unknown syscall '{name}'"))
+                            .span(ast.span),
+                    );
+                };
+
+                if self.gen_arguments(ast, args, &SYSCALL_REGISTERS) {
+                    self.emit(format!("mov rax, {number}"));
+                    self.emit("syscall");
+                }
+            },
+            ASTKind::Interrupt(val) => self.emit(format!("int {val}")),
+
+            ASTKind::HeapLiteral(_) => self.unsupported(ast, "heap literals"),
+            ASTKind::UnaryExpr(op, _) => self.unsupported(ast, &format!("{op:?}")),
+            _ => self.unsupported(ast, "this statement"),
+        }
+    }
+
+    // Leaves the address of `place` in rax
+    fn gen_address(&mut self, place: &AST) {
+        match &place.kind {
+            ASTKind::Identifier(name) => match self.lookup(name) {
+                Some(Local { location: Location::Stack(offset), .. }) => {
+                    let offset = *offset;
+                    self.emit(format!("lea rax, [rbp - {offset}]"));
+                },
+                Some(Local { location: Location::Register(_), .. }) =>
+                    self.unsupported(place, "the address of a register binding"),
+                None => self.emit(format!("lea rax, [rel {name}]")),
+            },
+            ASTKind::TypeAnnotation(_, inner) => self.gen_address(inner),
+            ASTKind::BinaryExpr(Operator::Index, base, index) =>
+                self.gen_element_address(base, index),
+            ASTKind::BinaryExpr(Operator::Access, base, field) =>
+                self.gen_field_address(base, field),
+            _ => self.unsupported(place, "the address of a temporary"),
+        }
+    }
+
+    // Leaves the address of `base.field` in rax, like elements fields are reached in place or
+    // through a pointer
+    fn gen_field_address(&mut self, base: &AST, field: &AST) {
+        let Some((offset, _, through_pointer)) =
+            self.type_of(base).and_then(|ty| self.field(&ty, field))
+        else {
+            return self.unsupported(base, "accessing this");
+        };
+
+        match through_pointer {
+            true => self.gen_expression(base),
+            false => self.gen_address(base),
+        }
+        if offset != 0 {
+            self.emit_immediate("add", "rax", offset);
+        }
+    }
+
+    // Leaves the address of `base[index]` in rax, arrays are indexed in place and pointers through
+    fn gen_element_address(&mut self, base: &AST, index: &AST) {
+        let Some((element, _, through_pointer)) = self.type_of(base).and_then(|ty| ty.indexed())
+        else {
+            return self.unsupported(base, "indexing this");
+        };
+        let size = element.size().unwrap_or(POINTER_SIZE);
+
+        match through_pointer {
+            true => self.gen_expression(base),
+            false => self.gen_address(base),
+        }
+
+        if let Some(index) = constant(index) {
+            if index != 0 {
+                self.emit_immediate("add", "rax", index * size);
+            }
+            return;
+        }
+
+        self.push("rax");
+        self.gen_expression(index);
+        self.pop("rcx");
+        match size {
+            1 | 2 | 4 | 8 => self.emit(format!("lea rax, [rcx + rax * {size}]")),
+            size => {
+                self.emit(format!("imul rax, rax, {size}"));
+                self.emit("add rax, rcx");
+            },
+        }
+    }
+
+    // The label of `name` instantiated with `types`, along with what its type parameters stand
+    // for. Each instance is generated as a routine of its own the first time it's needed.
+    fn instantiate(
+        &mut self, callee: &AST, name: &str, types: &[Type],
+    ) -> Option<(String, Vec<(String, Type)>)> {
+        // a generic routine calls others in terms of its own type parameters
+        let types = types.iter().map(|ty| ty.substitute(&self.substitution)).collect::<Vec<_>>();
+        let definition = self.generics.get(name)?.clone();
+        let ASTKind::FunctionDefinition(_, generics, attrs, params, _, body) = &definition.kind
+        else {
+            return None;
+        };
+        let substitution = generics
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(types.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let instances = self.instances.entry(name.to_string()).or_default();
+        if let Some(index) = instances.iter().position(|instance| *instance == types) {
+            return Some((format!("{name}.{index}"), substitution));
+        }
+        if instances.len() == MAX_INSTANCES {
+            self.report(
+                ReportKind::ExceededInstanceLimit
+                    .title(format!(
+                        "'{name}' is instantiated with over {MAX_INSTANCES} sets of types"
+                    ))
+                    .span(callee.span)
+                    .note(
+                        "HINT: Calling itself with types built from its own makes new ones forever",
+                    ),
+            );
+            return None;
+        }
+        instances.push(types);
+        let label = format!("{name}.{}", instances.len() - 1);
+
+        let params = params
+            .iter()
+            .map(|param| Parameter { ty: param.ty.substitute(&substitution), ..param.clone() })
+            .collect::<Vec<_>>();
+        if attrs.contains(&LabelAttribute::Inline) {
+            self.inlines.insert(label.clone(), (params.clone(), (**body).clone()));
+        }
+
+        let outer = std::mem::replace(&mut self.substitution, substitution.clone());
+        self.gen_routine(&definition, &label, attrs, &params, body);
+        self.substitution = outer;
+        Some((label, substitution))
+    }
+
+    // Evaluates left to right, then moves everything into place at once.
+    fn gen_arguments(&mut self, ast: &AST, args: &[AST], registers: &[&str]) -> bool {
+        if args.len() > registers.len() {
+            self.unsupported(ast, &format!("more than {} arguments", registers.len()));
+            return false;
+        }
+
+        for arg in args {
+            self.gen_expression(arg);
+            self.push("rax");
+        }
+        for register in registers[..args.len()].iter().rev() {
+            self.pop(register);
+        }
+        true
+    }
+
+    // C code expects rsp to be 16 byte aligned at the call, which the pushes so far may not have
+    // left it. The old rsp is pushed twice so the alignment holds, and restored from there.
+    // C code also leaves junk above narrow return values, so those are zero extended.
+    fn gen_external_call(&mut self, name: &str) {
+        self.emit("mov rax, rsp");
+        self.emit("and rsp, -16");
+        self.push("rax");
+        self.push("rax");
+        // aligning may have taken another slot
+        self.max_depth = self.max_depth.max(self.depth + 1);
+        // variadic functions take the number of vector registers used in al
+        self.emit("xor eax, eax");
+        self.emit(format!("call {name}"));
+        self.emit("mov rsp, [rsp]");
+        self.depth -= 2;
+
+        let ret = self.externs.iter().find(|(extern_name, _)| extern_name == name);
+        if let Some(size @ (1 | 2 | 4)) = ret.and_then(|(_, ret)| ret.as_ref()?.size()) {
+            self.extend(size);
+        }
+    }
+
+    fn pointee_size(&self, ast: &AST) -> usize {
+        self.type_of(ast)
+            .and_then(|ty| ty.pointee())
+            .and_then(|ty| ty.size())
+            .filter(|size| *size <= POINTER_SIZE)
+            .unwrap_or(POINTER_SIZE)
+    }
+
+    fn type_of(&self, ast: &AST) -> Option<Type> {
+        match &ast.kind {
+            ASTKind::Identifier(name) => self.lookup(name).map(|local| local.ty.clone()),
+            ASTKind::StringLiteral(_) =>
+                Some(Type::Heap { is_pointer: true, contents: vec![Type::Size(1)] }),
+            ASTKind::TypeAnnotation(ty, _) | ASTKind::Cast(ty, ..) =>
+                Some(ty.substitute(&self.substitution)),
+            ASTKind::UnaryExpr(Operator::Deref, inner) =>
+                self.type_of(inner).and_then(|ty| ty.pointee()),
+            ASTKind::BinaryExpr(Operator::Index, base, _) =>
+                self.type_of(base).and_then(|ty| ty.indexed()).map(|(element, ..)| element),
+            ASTKind::BinaryExpr(Operator::Access, base, field) =>
+                self.type_of(base).and_then(|ty| self.field(&ty, field)).map(|(_, ty, _)| ty),
+            ASTKind::BinaryExpr(Operator::Add | Operator::Substract, lhs, rhs) =>
+                self.type_of(lhs).or_else(|| self.type_of(rhs)),
+            _ => None,
+        }
+    }
+
+    // Stores rax into `target`
+    fn store_place(&mut self, target: &AST) {
+        match &target.kind {
+            ASTKind::Identifier(name) => self.store_local(name, "rax"),
+            ASTKind::UnaryExpr(Operator::Deref, inner) => {
+                let size = self.pointee_size(inner);
+                self.push("rax");
+                self.gen_expression(inner);
+                self.emit("mov rcx, rax");
+                self.pop("rax");
+                self.emit(format!("mov {} [rcx], {}", width(size), sized("rax", size)));
+            },
+            ASTKind::BinaryExpr(Operator::Index | Operator::Access, ..) => {
+                let size = self.type_of(target).and_then(|ty| ty.size()).unwrap_or(POINTER_SIZE);
+                self.push("rax");
+                self.gen_address(target);
+                self.emit("mov rcx, rax");
+                self.pop("rax");
+                self.emit(format!("mov {} [rcx], {}", width(size), sized("rax", size)));
+            },
+            ASTKind::TypeAnnotation(_, inner)
+            | ASTKind::UnaryExpr(Operator::MutatePre | Operator::MutatePost, inner) =>
+                self.store_place(inner),
+            _ => self.unsupported(target, "assigning to this"),
+        }
+    }
+
+    fn store_local(&mut self, name: &str, source: &str) {
+        let Some(Local { location, ty }) = self.lookup(name)
+        else {
+            return;
+        };
+
+        let size = ty.size().unwrap_or(POINTER_SIZE).min(POINTER_SIZE);
+        match *location {
+            Location::Stack(offset) =>
+                self.emit(format!("mov {} [rbp - {offset}], {}", width(size), sized(source, size))),
+            Location::Register(register) =>
+                self.emit(format!("mov {}, {}", sized(register, size), sized(source, size))),
+        }
+    }
+
+    fn load_local(&mut self, location: Location, size: usize) {
+        match location {
+            Location::Stack(offset) => self.load_memory(&format!("rbp - {offset}"), size),
+            Location::Register(register) => match size {
+                1 | 2 => self.emit(format!("movzx eax, {}", sized(register, size))),
+                4 => self.emit(format!("mov eax, {}", sized(register, size))),
+                _ => self.emit(format!("mov rax, {register}")),
+            },
+        }
+    }
+
+    // Zero extends the low `size` bytes of rax over the rest of it, integers are unsigned
+    fn extend(&mut self, size: usize) {
+        match size {
+            4 => self.emit("mov eax, eax"),
+            size => self.emit(format!("movzx eax, {}", sized("rax", size))),
+        }
+    }
+
+    // Sign extends the low `size` bytes of rax over the rest of it, only `cast` does this
+    fn sign_extend(&mut self, size: usize) {
+        match size {
+            4 => self.emit("movsxd rax, eax"),
+            size => self.emit(format!("movsx rax, {}", sized("rax", size))),
+        }
+    }
+
+    // Zero extends into rax
+    fn load_memory(&mut self, address: &str, size: usize) {
+        match size {
+            1 | 2 => self.emit(format!("movzx eax, {} [{address}]", width(size))),
+            4 => self.emit(format!("mov eax, dword [{address}]")),
+            _ => self.emit(format!("mov rax, qword [{address}]")),
+        }
+    }
+}
+
+// The value of an integer literal, looking through annotations
+fn constant(ast: &AST) -> Option<usize> {
+    match &ast.kind {
+        ASTKind::IntegerLiteral(val) => Some(*val),
+        ASTKind::TypeAnnotation(_, inner) => constant(inner),
+        _ => None,
+    }
+}
+
+fn width(size: usize) -> &'static str {
+    match size {
+        1 => "byte",
+        2 => "word",
+        4 => "dword",
+        _ => "qword",
+    }
+}
+
+// Names the lower `size` bytes of a 64 bit register
+fn sized(register: &str, size: usize) -> String {
+    match (register, size) {
+        ("rax" | "rbx" | "rcx" | "rdx", 1) => format!("{}l", &register[1..2]),
+        ("rax" | "rbx" | "rcx" | "rdx", 2) => format!("{}x", &register[1..2]),
+        ("rax" | "rbx" | "rcx" | "rdx", 4) => format!("e{}x", &register[1..2]),
+        ("rsi" | "rdi" | "rbp" | "rsp", 1) => format!("{}l", &register[1..]),
+        ("rsi" | "rdi" | "rbp" | "rsp", 2) => register[1..].to_string(),
+        ("rsi" | "rdi" | "rbp" | "rsp", 4) => format!("e{}", &register[1..]),
+        (_, 1) => format!("{register}b"),
+        (_, 2) => format!("{register}w"),
+        (_, 4) => format!("{register}d"),
+        _ => register.to_string(),
+    }
+}
+
+fn section(attrs: &[LabelAttribute]) -> Option<&str> {
+    attrs.iter().find_map(|attr| match attr {
+        LabelAttribute::Section(section) => Some(section.as_str()),
+        _ => None,
+    })
+}
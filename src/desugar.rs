@@ -0,0 +1,134 @@
+use crate::ast::{ASTKind, MatchArm, Operator, Program, AST};
+use crate::report::{LogHandler, Report, ReportKind};
+use crate::span::Span;
+
+pub struct Desugarer {
+    filename: &'static str,
+    handler:  LogHandler,
+}
+
+impl Desugarer {
+    pub fn new(filename: &'static str, handler: LogHandler) -> Self {
+        Self { filename, handler }
+    }
+
+    fn report(&self, report: Report) {
+        let (priority, log) = report.into();
+        self.handler.add_log(priority, log);
+    }
+
+    pub fn desugar(&self, program: &mut Program) {
+        program.stmts = self.desugar_all(std::mem::take(&mut program.stmts));
+    }
+
+    fn desugar_all(&self, nodes: Vec<AST>) -> Vec<AST> {
+        nodes.into_iter().map(|node| self.desugar_node(node)).collect()
+    }
+
+    fn desugar_boxed(&self, node: AST) -> Box<AST> {
+        Box::new(self.desugar_node(node))
+    }
+
+    fn desugar_node(&self, ast: AST) -> AST {
+        let AST { span, kind } = ast;
+
+        let kind = match kind {
+            ASTKind::BinaryExpr(Operator::Thread, value, target) =>
+                return self.thread(self.desugar_node(*value), self.desugar_node(*target), span),
+
+            ASTKind::FunctionDefinition(name, generics, attrs, params, ret, body) =>
+                ASTKind::FunctionDefinition(
+                    name,
+                    generics,
+                    attrs,
+                    params,
+                    ret,
+                    self.desugar_boxed(*body),
+                ),
+            ASTKind::OperatorDefinition(label, op, params, ret, body) =>
+                ASTKind::OperatorDefinition(label, op, params, ret, self.desugar_boxed(*body)),
+            ASTKind::VariableDefinition(name, ty, is_mutable, value) =>
+                ASTKind::VariableDefinition(
+                    name,
+                    ty,
+                    is_mutable,
+                    value.map(|value| self.desugar_boxed(*value)),
+                ),
+            ASTKind::Destructure(bindings, value) =>
+                ASTKind::Destructure(bindings, self.desugar_boxed(*value)),
+            ASTKind::Return(value) =>
+                ASTKind::Return(value.map(|value| self.desugar_boxed(*value))),
+            ASTKind::Loop(label, cond, body) => ASTKind::Loop(
+                label,
+                cond.map(|cond| self.desugar_boxed(*cond)),
+                self.desugar_boxed(*body),
+            ),
+            ASTKind::Conditional(cond, then, otherwise) => ASTKind::Conditional(
+                self.desugar_boxed(*cond),
+                self.desugar_boxed(*then),
+                otherwise.map(|otherwise| self.desugar_boxed(*otherwise)),
+            ),
+            ASTKind::Match(value, arms, is_exhaustive) => ASTKind::Match(
+                self.desugar_boxed(*value),
+                arms.into_iter()
+                    .map(|arm| MatchArm { body: self.desugar_node(arm.body), ..arm })
+                    .collect(),
+                is_exhaustive,
+            ),
+
+            ASTKind::BinaryExpr(op, lhs, rhs) =>
+                ASTKind::BinaryExpr(op, self.desugar_boxed(*lhs), self.desugar_boxed(*rhs)),
+            ASTKind::UnaryExpr(op, operand) => ASTKind::UnaryExpr(op, self.desugar_boxed(*operand)),
+            ASTKind::TypeAnnotation(ty, operand) =>
+                ASTKind::TypeAnnotation(ty, self.desugar_boxed(*operand)),
+            ASTKind::Cast(ty, value, is_explicit) =>
+                ASTKind::Cast(ty, self.desugar_boxed(*value), is_explicit),
+            ASTKind::Slice(base, lo, hi) => ASTKind::Slice(
+                self.desugar_boxed(*base),
+                lo.map(|lo| self.desugar_boxed(*lo)),
+                hi.map(|hi| self.desugar_boxed(*hi)),
+            ),
+
+            ASTKind::HeapLiteral(values) => ASTKind::HeapLiteral(self.desugar_all(values)),
+            ASTKind::Block(stmts) => ASTKind::Block(self.desugar_all(stmts)),
+            ASTKind::Syscall(name, args) => ASTKind::Syscall(name, self.desugar_all(args)),
+            ASTKind::Call(callee, args, is_external) =>
+                ASTKind::Call(self.desugar_boxed(*callee), self.desugar_all(args), is_external),
+
+            kind => kind,
+        };
+
+        kind.into_ast(span)
+    }
+
+    // `x => !f(y)` becomes `!f(x, y)`, the threaded value always goes first
+    fn thread(&self, value: AST, target: AST, span: Span) -> AST {
+        match target.kind {
+            ASTKind::Call(callee, mut args, is_external) => {
+                args.insert(0, value);
+                ASTKind::Call(callee, args, is_external).into_ast(span)
+            },
+            // `!f<T>` wraps its call in an annotation
+            ASTKind::TypeAnnotation(ty, inner) if matches!(inner.kind, ASTKind::Call(..)) =>
+                ASTKind::TypeAnnotation(ty, Box::new(self.thread(value, *inner, span)))
+                    .into_ast(span),
+
+            kind => {
+                let report = ReportKind::NotCallable
+                    .title("Cannot thread a value into something that isn't a call")
+                    .note("HINT: `x => !f(y)` is the same as `!f(x, y)`");
+
+                self.report(match kind {
+                    ASTKind::Identifier(_) => report
+                        .span(target.span.len(1).ghost('!'))
+                        .label("did you mean to call this?"),
+                    _ => report.span(target.span).label("this is not a call"),
+                });
+
+                let target = kind.into_ast(target.span);
+                ASTKind::BinaryExpr(Operator::Thread, Box::new(value), Box::new(target))
+                    .into_ast(span)
+            },
+        }
+    }
+}
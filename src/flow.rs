@@ -0,0 +1,91 @@
+use crate::cfg::{ControlFlowGraph, Terminator};
+use crate::report::{LogHandler, Report, ReportKind};
+use crate::span::Span;
+
+pub struct FlowAnalyzer {
+    filename: &'static str,
+    handler:  LogHandler,
+}
+
+impl FlowAnalyzer {
+    pub fn new(filename: &'static str, handler: LogHandler) -> Self {
+        Self { filename, handler }
+    }
+
+    fn report(&self, report: Report) {
+        let (priority, log) = report.into();
+        self.handler.add_log(priority, log);
+    }
+
+    pub fn analyze(&self, graph: &ControlFlowGraph) {
+        let reachable = graph.reachable();
+
+        // control flow inside a statement is lowered along with it, and a dead statement has
+        // dead branches inside it, neither of which is worth counting or reporting on its own
+        let mut reported: Vec<Span> = Vec::new();
+        for (block, _) in graph.blocks.iter().zip(&reachable).filter(|(_, r)| !**r) {
+            let mut stmts: Vec<Span> = Vec::new();
+            for stmt in &block.stmts {
+                if !reported.iter().chain(&stmts).any(|span| span.contains(&stmt.span)) {
+                    stmts.push(stmt.span);
+                }
+            }
+
+            let (Some(first), Some(last)) = (stmts.first(), stmts.last())
+            else {
+                continue;
+            };
+            let span = first.extend(last);
+            reported.push(span);
+
+            let label = match stmts.len() {
+                1 => String::from("this statement is never run"),
+                n => format!("this and the following {} statements are never run", n - 1),
+            };
+
+            self.report(
+                ReportKind::UnreachableCode
+                    .title("Unreachable code")
+                    .span(span)
+                    .label(label)
+                    .info(format!("dead code runs through line {}", last.end().0)),
+            );
+        }
+
+        let mut exits = graph.blocks.iter().zip(&reachable).filter(|(_, r)| **r).map(|(b, _)| b);
+        let name = graph.name.as_deref().unwrap_or("<global>");
+
+        // a bare `ret` is already rejected by the checker, so only falling off the end is left
+        if let Some(ret) = graph.returns {
+            if exits.any(|b| b.terminator == Terminator::Exit) {
+                let (line, end) = graph.span.end();
+                self.report(
+                    ReportKind::MissingReturn
+                        .title(format!("'{name}' can end without returning a value"))
+                        .span(Span::new(graph.span.filename, line, end.saturating_sub(1), 1))
+                        .label(format!("reached without returning `{ret:#}`"))
+                        .note("HINT: End every path through the function with `ret`"),
+                );
+            }
+            return;
+        }
+
+        let Some(valued) = exits
+            .clone()
+            .find(|b| b.terminator == Terminator::Return(true))
+            .and_then(|b| b.stmts.last())
+        else {
+            return;
+        };
+
+        if exits.any(|b| matches!(b.terminator, Terminator::Exit | Terminator::Return(false))) {
+            self.report(
+                ReportKind::MissingReturn
+                    .title(format!("Not all paths in '{name}' return a value"))
+                    .span(valued.span)
+                    .label("a value is returned here")
+                    .note("HINT: Paths that fall off the end or use a bare `ret` return nothing"),
+            );
+        }
+    }
+}
@@ -55,13 +55,15 @@ impl<'source> Lexer<'source> {
     }
 
     fn advance(&mut self) {
-        self.index += 1;
-        self.span.offset += 1;
-
         if Some("\n") == self.current() {
             self.span.line_number += 1;
             self.span.offset = 0;
         }
+        else {
+            self.span.offset += 1;
+        }
+
+        self.index += 1;
     }
 
     pub fn lex_tokens(&mut self) {
@@ -147,6 +149,10 @@ impl<'source> Lexer<'source> {
                         "op" => TokenKind::KeywordOp,
                         "cast" => TokenKind::KeywordCast,
                         "extern" => TokenKind::KeywordExtern,
+                        "loop" => TokenKind::KeywordLoop,
+                        "break" => TokenKind::KeywordBreak,
+                        "continue" => TokenKind::KeywordContinue,
+                        "match" => TokenKind::KeywordMatch,
                         _ => TokenKind::Identifier,
                     };
 
@@ -170,7 +176,7 @@ impl<'source> Lexer<'source> {
                                 self.report(
                                     ReportKind::UnterminatedStringLiteral
                                         .untitled()
-                                        .span(span.offset(span.offset - 2).len(self.index - index)),
+                                        .span(span.offset(span.offset - 1).len(self.index - index)),
                                 );
                                 continue 'outer;
                             },
@@ -197,9 +203,7 @@ impl<'source> Lexer<'source> {
                             "`" => {
                                 if self.index == start {
                                     self.report(
-                                        ReportKind::EmptyCharLiteral
-                                            .untitled()
-                                            .span(span.len(2).offset(span.offset - 1)),
+                                        ReportKind::EmptyCharLiteral.untitled().span(span.len(2)),
                                     );
                                     self.advance();
                                     continue 'outer;
@@ -229,7 +233,7 @@ impl<'source> Lexer<'source> {
                                 self.report(
                                     ReportKind::UnterminatedCharLiteral
                                         .untitled()
-                                        .span(span.len(self.index - index).offset(span.offset - 1)),
+                                        .span(span.len(self.index - index)),
                                 );
                                 continue 'outer;
                             },
@@ -241,13 +245,14 @@ impl<'source> Lexer<'source> {
                     self.push_token(
                         TokenKind::CharLiteral,
                         span.len(self.index - index),
-                        self.slice_source(index, self.index - index),
+                        // without the backticks
+                        self.slice_source(index + 1, self.index - index - 2),
                     );
 
                     continue;
                 },
 
-                "0" if self.peek().filter(|c| "box".contains(c)).is_some() => {
+                "0" if self.peek().is_some_and(|c| "box".contains(c)) => {
                     let (kind, base) = match self.peek() {
                         Some("b") => (TokenKind::BinaryIntLiteral, 2),
                         Some("o") => (TokenKind::OctalIntLiteral, 8),
@@ -263,7 +268,7 @@ impl<'source> Lexer<'source> {
 
                     self.push_token(
                         kind,
-                        self.span.len(self.index - index),
+                        span.len(self.index - index),
                         self.slice_source(index, self.index - index),
                     );
 
@@ -275,7 +280,8 @@ impl<'source> Lexer<'source> {
                         continue;
                     }
 
-                    if self.current() == Some(".") {
+                    // `1..5` is a range, not a float
+                    if self.current() == Some(".") && self.peek() != Some(".") {
                         self.advance();
                         if !self.lex_integer(10) {
                             continue;
@@ -308,7 +314,10 @@ impl<'source> Lexer<'source> {
                     continue;
                 },
 
-                "." => (TokenKind::Dot, 1),
+                "." => match self.peek() {
+                    Some(".") => (TokenKind::DotDot, 2),
+                    _ => (TokenKind::Dot, 1),
+                },
                 "'" => (TokenKind::Apostrophe, 1),
                 "~" => match self.peek() {
                     Some("=") => (TokenKind::NotEquals, 2),
@@ -387,9 +396,9 @@ impl<'source> Lexer<'source> {
 
         while let Some(c) = self.current() {
             match (base, c.to_ascii_lowercase().chars().next().unwrap()) {
-                (2, c) if CHARS[..1].contains(&c) => self.advance(),
-                (8, c) if CHARS[..7].contains(&c) => self.advance(),
-                (10, c) if CHARS[..9].contains(&c) => self.advance(),
+                (2, c) if CHARS[..2].contains(&c) => self.advance(),
+                (8, c) if CHARS[..8].contains(&c) => self.advance(),
+                (10, c) if CHARS[..10].contains(&c) => self.advance(),
                 (16, c) if CHARS.contains(&c) => self.advance(),
                 (_, '_') => self.advance(),
 
@@ -397,9 +406,16 @@ impl<'source> Lexer<'source> {
                     self.report(
                         ReportKind::SyntaxError
                             .title("Invalid Integer Literal")
-                            .span(self.span.len(1).offset(self.span.offset - 1))
+                            .span(self.span.len(1))
                             .label(format!("{c:?} not valid for base{base} Integer Literal")),
                     );
+                    // skip the rest of it, or lexing would stop here for good
+                    while self
+                        .current()
+                        .is_some_and(|c| c.chars().all(|c| c.is_ascii_alphanumeric()))
+                    {
+                        self.advance();
+                    }
                     return false;
                 },
 
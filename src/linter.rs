@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use crate::ast::{ASTKind, ArrayLength, LabelAttribute, Operator, Parameter, Pattern, Program,
+                 Type, AST};
+use crate::report::{LogHandler, Report, ReportKind};
+use crate::span::Span;
+
+struct Binding {
+    name:       String,
+    span:       Span,
+    is_read:    bool,
+    is_written: bool,
+}
+
+pub struct Linter {
+    handler: LogHandler,
+
+    scopes:       Vec<Vec<Binding>>,
+    labels:       Vec<(String, Span)>,
+    label_refs:   HashSet<String>,
+    // whether the node being linted is a statement, whose value is thrown away
+    is_statement: bool,
+}
+
+impl Linter {
+    pub fn new(handler: LogHandler) -> Self {
+        Self {
+            handler,
+            scopes: Vec::new(),
+            labels: Vec::new(),
+            label_refs: HashSet::new(),
+            is_statement: false,
+        }
+    }
+
+    fn report(&self, report: Report) {
+        let (priority, log) = report.into();
+        self.handler.add_log(priority, log);
+    }
+
+    pub fn lint(&mut self, program: &Program) {
+        self.lint_block(&program.stmts, false);
+
+        for (name, span) in &self.labels {
+            if !self.label_refs.contains(name) {
+                self.report(
+                    ReportKind::UnusedLabel
+                        .title(format!("Label '{name}' is never used"))
+                        .span(*span)
+                        .note("HINT: Mark it as `entry` or `export` if it's called from outside"),
+                );
+            }
+        }
+    }
+
+    // A block used as a value, like a branch of `x <- c ? { a } : { b }`, yields its last statement
+    fn lint_block(&mut self, stmts: &[AST], yields: bool) {
+        self.scopes.push(Vec::new());
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            // the left of a `;` gets its own lint, only what it yields is discarded here
+            let mut yielded = stmt;
+            while let ASTKind::BinaryExpr(Operator::Sequence, _, rhs) = &yielded.kind {
+                yielded = rhs;
+            }
+
+            let is_used = yields && i == stmts.len() - 1;
+            if !is_used && !yielded.has_side_effects() {
+                self.report(
+                    ReportKind::UnusedValue
+                        .title("Result of expression is discarded")
+                        .span(yielded.span)
+                        .label("this value is never used"),
+                );
+            }
+
+            self.is_statement = true;
+            self.lint_node(stmt);
+        }
+
+        let scope = self.scopes.pop().expect("Linter has no scope");
+        self.report_unused(scope);
+    }
+
+    fn report_unused(&self, scope: Vec<Binding>) {
+        for binding in scope {
+            if binding.is_read {
+                continue;
+            }
+
+            let title = match binding.is_written {
+                true => format!("Binding '{}' is written to but never read", binding.name),
+                false => format!("Unused binding '{}'", binding.name),
+            };
+            self.report(ReportKind::UnusedBinding.title(title).span(binding.span));
+        }
+    }
+
+    fn lint_node(&mut self, ast: &AST) {
+        let is_statement = std::mem::take(&mut self.is_statement);
+        match &ast.kind {
+            ASTKind::VariableDefinition(name, ty, _, value) => {
+                self.lint_type(ty);
+                if let Some(value) = value {
+                    self.lint_node(value);
+                }
+
+                self.scopes.last_mut().expect("Linter has no scope").push(Binding {
+                    name:       name.clone(),
+                    span:       ast.span,
+                    is_read:    false,
+                    is_written: value.is_some(),
+                });
+            },
+
+            ASTKind::LabelDefinition(Some(name), attrs) =>
+                if !is_external(attrs) {
+                    self.labels.push((name.clone(), ast.span));
+                },
+            ASTKind::FunctionDefinition(name, _, attrs, params, ret, body) => {
+                if !is_external(attrs) {
+                    self.labels.push((name.clone(), ast.span));
+                }
+                if let Some(ret) = ret {
+                    self.lint_type(ret);
+                }
+                self.lint_routine(params, body);
+            },
+
+            ASTKind::Destructure(bindings, value) => {
+                self.lint_node(value);
+                for binding in bindings {
+                    if let Some(ty) = &binding.ty {
+                        self.lint_type(ty);
+                    }
+                    self.scopes.last_mut().expect("Linter has no scope").push(Binding {
+                        name:       binding.name.clone(),
+                        span:       binding.span,
+                        is_read:    false,
+                        is_written: true,
+                    });
+                }
+            },
+            ASTKind::OperatorDefinition(_, _, params, ret, body) => {
+                self.lint_type(ret);
+                self.lint_routine(params, body);
+            },
+
+            ASTKind::Identifier(name) => match self.lookup(name) {
+                Some(binding) => binding.is_read = true,
+                None => {
+                    self.label_refs.insert(name.clone());
+                },
+            },
+            ASTKind::Instance(name, _) => {
+                self.label_refs.insert(name.clone());
+            },
+
+            ASTKind::BinaryExpr(Operator::Assign, target, value) => {
+                self.lint_node(value);
+                self.lint_target(target);
+            },
+            ASTKind::UnaryExpr(
+                Operator::MutatePre
+                | Operator::MutatePost
+                | Operator::Increment
+                | Operator::Decrement,
+                target,
+            ) => self.lint_target(target),
+
+            ASTKind::BinaryExpr(Operator::Sequence, lhs, rhs) => {
+                if !lhs.has_side_effects() {
+                    self.report(
+                        ReportKind::NoEffectSequence
+                            .title("Left side of `;` has no effect")
+                            .span(lhs.span)
+                            .label("this value is thrown away")
+                            .note(
+                                "HINT: `a; b` only runs `a` for its side effects, then yields `b`",
+                            ),
+                    );
+                }
+
+                self.lint_node(lhs);
+                self.lint_node(rhs);
+            },
+            // the right of `.` names a field rather than a binding
+            ASTKind::BinaryExpr(Operator::Access, base, _) => self.lint_node(base),
+            ASTKind::BinaryExpr(_, lhs, rhs) => {
+                self.lint_node(lhs);
+                self.lint_node(rhs);
+            },
+            ASTKind::TypeAnnotation(ty, operand) | ASTKind::Cast(ty, operand, _) => {
+                self.lint_type(ty);
+                self.lint_node(operand);
+            },
+            ASTKind::SizeOf(ty) | ASTKind::TypeAlias(_, ty) => self.lint_type(ty),
+            ASTKind::StructDefinition(_, fields) =>
+                fields.iter().for_each(|field| self.lint_type(&field.ty)),
+            ASTKind::EnumDefinition(_, ty, variants) => {
+                self.lint_type(ty);
+                variants.iter().filter_map(|variant| variant.value.as_ref()).for_each(|value| {
+                    self.lint_node(value);
+                });
+            },
+            ASTKind::UnaryExpr(_, operand) | ASTKind::Return(Some(operand)) =>
+                self.lint_node(operand),
+
+            ASTKind::Block(stmts) => self.lint_block(stmts, !is_statement),
+            ASTKind::Loop(_, cond, body) => {
+                if let Some(cond) = cond {
+                    self.lint_node(cond);
+                }
+                self.is_statement = true;
+                self.lint_node(body);
+            },
+            // the branches are used the same way as the whole
+            ASTKind::Conditional(cond, then, otherwise) => {
+                self.lint_node(cond);
+                self.is_statement = is_statement;
+                self.lint_node(then);
+                if let Some(otherwise) = otherwise {
+                    self.is_statement = is_statement;
+                    self.lint_node(otherwise);
+                }
+            },
+            ASTKind::Match(value, arms, _) => {
+                self.lint_node(value);
+                for arm in arms {
+                    arm.patterns.iter().for_each(|pattern| match pattern {
+                        Pattern::Wildcard(_) => {},
+                        Pattern::Value(value) => self.lint_node(value),
+                        Pattern::Range(lo, hi) => {
+                            self.lint_node(lo);
+                            self.lint_node(hi);
+                        },
+                    });
+                    self.is_statement = is_statement;
+                    self.lint_node(&arm.body);
+                }
+            },
+
+            ASTKind::HeapLiteral(values) | ASTKind::Syscall(_, values) =>
+                values.iter().for_each(|value| self.lint_node(value)),
+            ASTKind::Call(callee, args, _) => {
+                self.lint_node(callee);
+                args.iter().for_each(|arg| self.lint_node(arg));
+            },
+
+            _ => {},
+        }
+    }
+
+    // Parameters only count as used once the body reads them.
+    fn lint_routine(&mut self, params: &[Parameter], body: &AST) {
+        params.iter().for_each(|param| self.lint_type(&param.ty));
+        self.scopes.push(
+            params
+                .iter()
+                .map(|param| Binding {
+                    name:       param.name.clone(),
+                    span:       param.span,
+                    is_read:    false,
+                    is_written: false,
+                })
+                .collect(),
+        );
+        self.is_statement = true;
+        self.lint_node(body);
+        let scope = self.scopes.pop().expect("Linter has no scope");
+        self.report_unused(scope);
+    }
+
+    // Constants used in array lengths count as reads.
+    fn lint_type(&mut self, ty: &Type) {
+        match ty {
+            Type::Array { inner, elems } => {
+                self.lint_type(inner);
+                if let Some(ArrayLength::Expr(expr, _)) = elems {
+                    self.lint_node(expr);
+                }
+            },
+            Type::Heap { contents, .. } => contents.iter().for_each(|ty| self.lint_type(ty)),
+            Type::Register { inner: Some(inner), .. } => self.lint_type(inner),
+            // linted where the alias is defined
+            Type::Size(_)
+            | Type::Struct(..)
+            | Type::Alias(..)
+            | Type::Register { inner: None, .. } => {},
+        }
+    }
+
+    // A write to a binding doesn't count as using it.
+    fn lint_target(&mut self, target: &AST) {
+        match &target.kind {
+            ASTKind::Identifier(name) =>
+                if let Some(binding) = self.lookup(name) {
+                    binding.is_written = true;
+                },
+            ASTKind::BinaryExpr(Operator::Access, base, _) | ASTKind::TypeAnnotation(_, base) =>
+                self.lint_target(base),
+            _ => self.lint_node(target),
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.iter_mut().rfind(|b| b.name == name))
+    }
+}
+
+// Labels reachable from outside the program are never unused
+fn is_external(attrs: &[LabelAttribute]) -> bool {
+    attrs.iter().any(|attr| matches!(attr, LabelAttribute::Entry | LabelAttribute::Export))
+}
@@ -27,19 +27,33 @@
 
 use colored::Colorize;
 
+use crate::cfg::ControlFlowGraph;
+use crate::checker::Checker;
+use crate::codegen::Generator;
+use crate::desugar::Desugarer;
+use crate::flow::FlowAnalyzer;
 use crate::lexer::Lexer;
+use crate::linter::Linter;
 use crate::parser::Parser;
-use crate::report::{Level, LogHandler, Report};
+use crate::report::{Level, LogHandler, Report, ReportKind};
 use crate::scanner::Scanner;
+use crate::stack::StackUsage;
 
 mod args;
 mod ast;
+mod cfg;
+mod checker;
+mod codegen;
+mod desugar;
+mod flow;
 mod lexer;
+mod linter;
 mod parser;
 // mod preprocessor;
 mod report;
 mod scanner;
 mod span;
+mod stack;
 mod token;
 
 fn main() {
@@ -50,6 +64,10 @@ fn main() {
     }
 
     let handler = LogHandler::new();
+    handler.set_log_limit(match *args.level {
+        Level::Silent => 0,
+        level => level as usize + 1,
+    });
 
     let tokens = {
         let mut lexer = Lexer::new(*args.file, Scanner::get(*args.file), handler.clone());
@@ -61,7 +79,7 @@ fn main() {
             lexer.tokens.as_cursor().for_each(|token| println!("{token:#}"));
         }
 
-        if handler.test_ge_log(Level::Warn as u8 as usize) {
+        if handler.test_ge_log(Level::Error as u8 as usize) {
             std::process::exit(1);
         }
 
@@ -88,7 +106,7 @@ fn main() {
     //     (tokens, tags)
     // };
 
-    let program = {
+    let mut program = {
         let mut parser = Parser::new(&args.file, tokens, handler.clone());
         let result = parser.parse();
 
@@ -97,12 +115,99 @@ fn main() {
             result.stmts.iter().for_each(|stmt| println!("{stmt:#}"));
         }
 
-        if handler.test_ge_log(Level::Warn as u8 as usize) {
+        if let Some((span, arch)) = &result.metadata.arch {
+            if !arch.iter().any(|arch| arch == codegen::ARCH) {
+                let (priority, log) = ReportKind::UnsupportedArch
+                    .title("None of the target architectures are supported")
+                    .span(*span)
+                    .note(format!("HINT: Only {} can be compiled for", codegen::ARCH))
+                    .into();
+                handler.add_log(priority, log);
+            }
+        }
+
+        if handler.test_ge_log(Level::Error as u8 as usize) {
             std::process::exit(1);
         }
 
         result
     };
 
+    {
+        let desugarer = Desugarer::new(*args.file, handler.clone());
+        desugarer.desugar(&mut program);
+
+        if *args.debug {
+            println!("\n{}", "DESUGAR".bold());
+            program.stmts.iter().for_each(|stmt| println!("{stmt:#}"));
+        }
+
+        if handler.test_ge_log(Level::Error as u8 as usize) {
+            std::process::exit(1);
+        }
+    };
+
+    {
+        let mut checker = Checker::new(*args.file, handler.clone());
+        checker.check(&mut program);
+
+        if handler.test_ge_log(Level::Error as u8 as usize) {
+            std::process::exit(1);
+        }
+    };
+
+    {
+        let mut linter = Linter::new(handler.clone());
+        linter.lint(&program);
+    };
+
+    {
+        let graphs = ControlFlowGraph::build_all(&program);
+
+        if *args.debug {
+            println!("\n{}", "CFG".bold());
+            graphs.iter().for_each(|graph| println!("{graph}"));
+        }
+
+        let analyzer = FlowAnalyzer::new(*args.file, handler.clone());
+        graphs.iter().for_each(|graph| analyzer.analyze(graph));
+
+        if handler.test_ge_log(Level::Error as u8 as usize) {
+            std::process::exit(1);
+        }
+    };
+
+    {
+        let mut generator = Generator::new(*args.file, handler.clone());
+        let asm = generator.generate(&program);
+
+        if *args.debug {
+            println!("\n{}", "CODEGEN".bold());
+            println!("{asm}");
+        }
+
+        if handler.test_ge_log(Level::Error as u8 as usize) {
+            std::process::exit(1);
+        }
+
+        if *args.stack_usage {
+            println!("{}", StackUsage::analyze(generator.frames()));
+        }
+
+        // `:name` only picks the output when it isn't given on the command line
+        let output = match &program.metadata.name {
+            Some((_, name)) if !args.output.is_set() => format!("{name}.asm"),
+            _ => args.output.to_string(),
+        };
+
+        if std::fs::write(&output, asm).is_err() {
+            let (priority, log) =
+                ReportKind::IOError.title(format!("Failed to write file: '{output}'")).into();
+            handler.add_log(priority, log);
+            handler.test_ge_log(Level::Error as u8 as usize);
+            std::process::exit(1);
+        }
+    };
+
     handler.terminate();
 }
@@ -6,7 +6,9 @@ use std::str;
 
 use iterlist::IterList;
 
-use crate::ast::{ASTKind, LabelAttribute, Operator, Program, Type, AST};
+use crate::ast::{ASTKind, ArrayLength, DestructureBinding, EnumVariant, LabelAttribute, MatchArm,
+                 Operator, Parameter, Pattern, Program, ProgramMetadata, StructField, Type,
+                 TypeParameter, AST, POINTER_SIZE};
 use crate::report::{LogHandler, Report, ReportKind, Result};
 use crate::span::Span;
 use crate::token::{Token, TokenKind};
@@ -15,13 +17,16 @@ pub struct Parser<'contents> {
     filename: &'static str,
     tokens:   IterList<Token<'contents>>,
     handler:  LogHandler,
+
+    // operators defined so far, for naming their routines
+    operators: usize,
 }
 
 impl<'contents> Parser<'contents> {
     pub fn new(
         filename: &'static str, tokens: IterList<Token<'contents>>, handler: LogHandler,
     ) -> Self {
-        Self { filename, tokens, handler }
+        Self { filename, tokens, handler, operators: 0 }
     }
 
     fn report(&self, report: Report) {
@@ -42,10 +47,10 @@ impl<'contents> Parser<'contents> {
     }
 
     fn advance(&mut self) {
-        assert!(!self.tokens.advance(), "Failed to advance: Out of bounds");
+        assert!(self.tokens.advance(), "Failed to advance: Out of bounds");
     }
 
-    fn consume(&mut self, kind: TokenKind, msg: &'static str) -> Result<Token> {
+    fn consume(&mut self, kind: TokenKind, msg: &'static str) -> Result<Token<'contents>> {
         let Token { kind: actual, span, .. } = self.current();
         match actual {
             k if k == kind => {
@@ -91,10 +96,9 @@ impl<'contents> Parser<'contents> {
                 kind if kind == until => break,
                 TokenKind::NewLine => break,
                 TokenKind::EOF => return,
-                _ => continue,
+                _ => {},
             }
         }
-        return;
     }
 
     pub fn parse(&mut self) -> Program {
@@ -102,7 +106,52 @@ impl<'contents> Parser<'contents> {
         else {
             unreachable!("Can't happen nerds!")
         };
-        Program { stmts, filename: self.filename }
+        let metadata = self.collect_metadata(&stmts);
+        Program { stmts, filename: self.filename, metadata }
+    }
+
+    fn collect_metadata(&self, stmts: &[AST]) -> ProgramMetadata {
+        let mut metadata = ProgramMetadata::default();
+        for stmt in stmts {
+            let ASTKind::Tag(name, args) = &stmt.kind
+            else {
+                continue;
+            };
+
+            // the arguments were already validated by `parse_tag`
+            let first = match name.as_str() {
+                "name" => match &args[0].kind {
+                    ASTKind::StringLiteral(text) =>
+                        metadata.name.replace((stmt.span, text.clone())).map(|(span, _)| span),
+                    _ => unreachable!("`:name` takes a string"),
+                },
+                "arch" => {
+                    let arch = args
+                        .iter()
+                        .filter_map(|arg| match &arg.kind {
+                            ASTKind::Identifier(arch) => Some(arch.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    metadata.arch.replace((stmt.span, arch)).map(|(span, _)| span)
+                },
+                _ => {
+                    metadata.custom.push((name.clone(), args.clone()));
+                    None
+                },
+            };
+
+            if let Some(first) = first {
+                self.report(
+                    ReportKind::DuplicateTag
+                        .title(format!("Tag ':{name}' is already set"))
+                        .span(stmt.span)
+                        .label("set again here")
+                        .info(format!("first set at {first}")),
+                );
+            }
+        }
+        metadata
     }
 
     fn parse_block(&mut self, global: bool) -> AST {
@@ -110,10 +159,21 @@ impl<'contents> Parser<'contents> {
         let until = if global { TokenKind::EOF } else { TokenKind::RBrace };
         let start = self.current().span;
 
-        while self.current().kind != until {
+        while !matches!(self.current().kind, TokenKind::EOF) && self.current().kind != until {
             match self.parse_statement() {
                 Ok(val) => {
+                    if !global && matches!(val.kind, ASTKind::Tag(..)) {
+                        self.report(
+                            ReportKind::InvalidTag
+                                .title("Tags can only be used at the top level")
+                                .span(val.span),
+                        );
+                    }
                     stmts.push(val);
+                    // `{ ret x }` needs no newline before the brace
+                    if self.current().kind == until {
+                        continue;
+                    }
                     self.consume_newline().map_err(|err| {
                         self.report(*err);
                         self.synchronize(until);
@@ -123,24 +183,48 @@ impl<'contents> Parser<'contents> {
                     self.report(*report);
                     self.synchronize(until);
                 },
-            };
+            }
         }
 
+        let mut end = stmts.last().map_or(start, |ast| ast.span);
         if !global {
-            self.consume(until, "block not terminated");
-        };
-
-        let end = start.extend(&stmts.last().map_or_else(|| start, |ast| ast.span));
+            // the closing brace is where control leaves the block
+            if self.current().kind == until {
+                end = self.current().span;
+            }
+            if let Err(report) = self.consume(until, "block not terminated") {
+                self.report(*report);
+            }
+        }
+        let end = start.extend(&end);
 
         ASTKind::Block(stmts).into_ast(start.extend(&end))
     }
 
     fn parse_statement(&mut self) -> Result<AST> {
         match self.current().kind {
-            // TokenKind::Colon      => self.parse_tag(),
+            TokenKind::Colon => self.parse_tag(),
             TokenKind::Star => self.parse_interrupt(),
-            // TokenKind::Identifier => self.parse_label(),
             TokenKind::KeywordRet => self.parse_return(),
+            TokenKind::KeywordStruct => self.parse_struct(),
+            TokenKind::KeywordEnum => self.parse_enum(),
+            TokenKind::KeywordType => self.parse_type_alias(),
+            TokenKind::KeywordOp => self.parse_operator(),
+            TokenKind::KeywordDestr => self.parse_destructure(),
+            TokenKind::KeywordExtern => self.parse_extern(),
+            TokenKind::KeywordLoop => self.parse_loop(),
+            TokenKind::KeywordBreak | TokenKind::KeywordContinue => Ok(self.parse_loop_control()),
+            TokenKind::Identifier if self.is_label_definition() => self.parse_label(),
+            TokenKind::Identifier if self.is_function_definition() =>
+                self.parse_function_definition(),
+            TokenKind::Identifier
+                if Self::is_type_start(self.get(1).kind)
+                    && !(self.get(1).kind == TokenKind::LBracket && self.touches_previous(1)) =>
+                self.parse_variable_definition(),
+            TokenKind::Apostrophe
+                if self.get(1).kind == TokenKind::Identifier
+                    && Self::is_type_start(self.get(2).kind) =>
+                self.parse_variable_definition(),
             // HACK: this is temporary, this should parse assignments
             // Update: changed to another token because i needed % for modulo
             // test cases should still pass just change it
@@ -157,15 +241,608 @@ impl<'contents> Parser<'contents> {
         }
     }
 
+    // :<ident> [<arg>...]
+    fn parse_tag(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        let Token { kind, span, text: name } =
+            self.consume(TokenKind::Colon, "tags start with `:`")?;
+        if kind != TokenKind::Identifier {
+            return ReportKind::UnexpectedToken
+                .title(format!("expected 'Identifier' got '{kind:?}'"))
+                .span(span)
+                .label("expected a tag name, e.g. `:name`")
+                .as_err();
+        }
+        self.advance();
+
+        let mut args = Vec::new();
+        while !matches!(
+            self.current().kind,
+            TokenKind::NewLine | TokenKind::EOF | TokenKind::RBrace
+        ) {
+            args.push(self.parse_expression()?);
+        }
+
+        let span = start.extend(&self.get(-1).span);
+        let (is_valid, shape) = match name {
+            "name" => (
+                matches!(args.as_slice(), [AST { kind: ASTKind::StringLiteral(_), .. }]),
+                "a single string, e.g. `:name \"hello\"`",
+            ),
+            "arch" => (
+                !args.is_empty()
+                    && args.iter().all(|arg| matches!(arg.kind, ASTKind::Identifier(_))),
+                "one or more architectures, e.g. `:arch x86_64`",
+            ),
+            // anything goes for custom tags
+            _ => (true, ""),
+        };
+
+        if !is_valid {
+            return ReportKind::InvalidTag
+                .title(format!("Invalid arguments to ':{name}'"))
+                .span(span)
+                .label(format!("expected {shape}"))
+                .as_err();
+        }
+
+        Ok(ASTKind::Tag(name.to_string(), args).into_ast(span))
+    }
+
     fn parse_return(&mut self) -> Result<AST> {
-        if self.get(1).kind == TokenKind::NewLine {
+        let start = self.current().span;
+        if matches!(self.get(1).kind, TokenKind::NewLine | TokenKind::EOF | TokenKind::RBrace) {
             self.advance();
-            return Ok(ASTKind::Return(None).into_ast(self.current().span));
+            return Ok(ASTKind::Return(None).into_ast(start));
         }
 
         self.advance();
         let expr = self.parse_expression()?;
-        Ok(ASTKind::Return(Some(expr.into())).into_ast(self.current().span))
+        let span = start.extend(&expr.span);
+        Ok(ASTKind::Return(Some(expr.into())).into_ast(span))
+    }
+
+    // struct <ident> { <ident> <type>, ... }
+    fn parse_struct(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let Token { kind, span: name_span, text: name } = self.current();
+        if kind != TokenKind::Identifier {
+            return ReportKind::UnexpectedToken
+                .title("Expected struct name")
+                .span(name_span)
+                .as_err();
+        }
+        self.advance();
+        self.consume(TokenKind::LBrace, "struct fields go within braces")?;
+
+        let mut fields = Vec::new();
+        loop {
+            while self.current().kind == TokenKind::NewLine {
+                self.advance();
+            }
+            if self.current().kind == TokenKind::RBrace {
+                break;
+            }
+
+            let Token { kind, span, text } = self.current();
+            if kind != TokenKind::Identifier {
+                return ReportKind::UnexpectedToken
+                    .title("Expected field name")
+                    .span(span)
+                    .as_err();
+            }
+            self.advance();
+
+            let ty = self.parse_type()?;
+            let span = span.extend(&self.current().span);
+            self.advance();
+            fields.push(StructField { name: text.to_string(), ty, span });
+
+            match self.current().kind {
+                TokenKind::Comma | TokenKind::NewLine => self.advance(),
+                TokenKind::RBrace => {},
+                _ =>
+                    return ReportKind::SyntaxError
+                        .title("Expected comma between fields")
+                        .span(self.current().span)
+                        .as_err(),
+            }
+        }
+        self.advance();
+
+        let span = start.extend(&name_span);
+        if fields.is_empty() {
+            return ReportKind::SyntaxError
+                .title("Zero-sized structs are disallowed")
+                .span(span)
+                .as_err();
+        }
+
+        Ok(ASTKind::StructDefinition(name.to_string(), fields).into_ast(span))
+    }
+
+    // enum <ident> [<size>] { <ident> [<- <expr>], ... }
+    fn parse_enum(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let Token { kind, span: name_span, text: name } = self.current();
+        if kind != TokenKind::Identifier {
+            return ReportKind::UnexpectedToken
+                .title("Expected enum name")
+                .span(name_span)
+                .as_err();
+        }
+        self.advance();
+
+        let ty = match self.current().kind {
+            TokenKind::LBrace => Type::Size(POINTER_SIZE),
+            _ => {
+                let ty = self.parse_type()?;
+                self.advance();
+                ty
+            },
+        };
+        self.consume(TokenKind::LBrace, "enum variants go within braces")?;
+
+        let mut variants = Vec::new();
+        loop {
+            while self.current().kind == TokenKind::NewLine {
+                self.advance();
+            }
+            if self.current().kind == TokenKind::RBrace {
+                break;
+            }
+
+            let Token { kind, span, text } = self.current();
+            if kind != TokenKind::Identifier {
+                return ReportKind::UnexpectedToken
+                    .title("Expected variant name")
+                    .span(span)
+                    .as_err();
+            }
+            self.advance();
+
+            let value = match self.current().kind {
+                TokenKind::ArrowLeft => {
+                    self.advance();
+                    Some(self.parse_expression()?)
+                },
+                _ => None,
+            };
+
+            let span = value.as_ref().map_or(span, |value| span.extend(&value.span));
+            variants.push(EnumVariant { name: text.to_string(), value, span });
+
+            match self.current().kind {
+                TokenKind::Comma | TokenKind::NewLine => self.advance(),
+                TokenKind::RBrace => {},
+                _ =>
+                    return ReportKind::SyntaxError
+                        .title("Expected comma between variants")
+                        .span(self.current().span)
+                        .as_err(),
+            }
+        }
+        self.advance();
+
+        let span = start.extend(&name_span);
+        if variants.is_empty() {
+            return ReportKind::SyntaxError
+                .title("Enums need at least one variant")
+                .span(span)
+                .as_err();
+        }
+
+        Ok(ASTKind::EnumDefinition(name.to_string(), ty, variants).into_ast(span))
+    }
+
+    // type <ident> <type>
+    fn parse_type_alias(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let Token { kind, span: name_span, text: name } = self.current();
+        if kind != TokenKind::Identifier {
+            return ReportKind::UnexpectedToken
+                .title("Expected type name")
+                .span(name_span)
+                .as_err();
+        }
+        self.advance();
+
+        if !Self::is_type_start(self.current().kind) {
+            return ReportKind::SyntaxError
+                .title(format!("Expected a type for '{name}' to stand for"))
+                .span(self.current().span)
+                .note(format!("HINT: e.g. `type {name} [1]`"))
+                .as_err();
+        }
+        let ty = self.parse_type()?;
+        self.advance();
+
+        Ok(ASTKind::TypeAlias(name.to_string(), ty).into_ast(start.extend(&name_span)))
+    }
+
+    // destr { ['] <ident> [<type>], ... } <- <expr>
+    fn parse_destructure(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+        self.consume(TokenKind::LBrace, "names to destructure into go within braces")?;
+
+        let mut bindings = Vec::new();
+        while self.current().kind != TokenKind::RBrace {
+            let is_mutable = self.current().kind == TokenKind::Apostrophe;
+            if is_mutable {
+                self.advance();
+            }
+
+            let Token { kind, span, text } = self.current();
+            if kind != TokenKind::Identifier {
+                return ReportKind::UnexpectedToken
+                    .title("Expected a name to destructure into")
+                    .span(span)
+                    .as_err();
+            }
+            self.advance();
+
+            let (ty, span) = match Self::is_type_start(self.current().kind) {
+                true => {
+                    let ty = self.parse_type()?;
+                    let span = span.extend(&self.current().span);
+                    self.advance();
+                    (Some(ty), span)
+                },
+                false => (None, span),
+            };
+            bindings.push(DestructureBinding { name: text.to_string(), ty, is_mutable, span });
+
+            match self.current().kind {
+                TokenKind::Comma => self.advance(),
+                TokenKind::RBrace => {},
+                _ =>
+                    return ReportKind::SyntaxError
+                        .title("Expected comma between names")
+                        .span(self.current().span)
+                        .as_err(),
+            }
+        }
+
+        if bindings.is_empty() {
+            return ReportKind::SyntaxError
+                .title("Nothing to destructure into")
+                .span(start.extend(&self.current().span))
+                .as_err();
+        }
+        self.advance();
+
+        self.consume(TokenKind::ArrowLeft, "destructuring needs a value")?;
+        let value = self.parse_expression()?;
+
+        let span = start.extend(&value.span);
+        Ok(ASTKind::Destructure(bindings, Box::new(value)).into_ast(span))
+    }
+
+    // loop [<ident>:] [<expr>] { ... }
+    fn parse_loop(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let mut label = None;
+        if self.current().kind == TokenKind::Identifier && self.get(1).kind == TokenKind::Colon {
+            label = Some(self.current().text.to_string());
+            self.advance();
+            self.advance();
+        }
+
+        let cond = match self.current().kind {
+            TokenKind::LBrace => None,
+            _ => Some(Box::new(self.parse_expression()?)),
+        };
+
+        let span = start.extend(&self.get(-1).span);
+        let body = self.parse_body()?;
+        Ok(ASTKind::Loop(label, cond, Box::new(body)).into_ast(span))
+    }
+
+    // match <expr> { [|] <pattern>, ... => <expr> | ... }
+    fn parse_match(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let value = self.parse_expression()?;
+        self.consume(TokenKind::LBrace, "match arms go within braces")?;
+        while self.current().kind == TokenKind::NewLine {
+            self.advance();
+        }
+
+        if self.current().kind == TokenKind::RBrace {
+            return ReportKind::SyntaxError
+                .title("Match has no arms")
+                .span(start.extend(&self.current().span))
+                .note("HINT: Add one with `_ => ...`")
+                .as_err();
+        }
+
+        // the first arm may have a `|` too, so they all line up
+        if self.current().kind == TokenKind::Pipe {
+            self.advance();
+        }
+
+        let mut arms: Vec<MatchArm> = Vec::new();
+        loop {
+            while self.current().kind == TokenKind::NewLine {
+                self.advance();
+            }
+
+            if arms.last().is_some_and(is_wildcard_arm) {
+                return ReportKind::SyntaxError
+                    .title("Arms after `_` can never match")
+                    .span(self.current().span)
+                    .note("HINT: Move the `_` arm to the end")
+                    .as_err();
+            }
+
+            let arm_start = self.current().span;
+            let mut patterns = vec![self.parse_pattern()?];
+            while self.current().kind == TokenKind::Comma {
+                self.advance();
+                patterns.push(self.parse_pattern()?);
+            }
+
+            let wildcard = patterns.iter().find(|pattern| matches!(pattern, Pattern::Wildcard(_)));
+            if let Some(wildcard) = wildcard.filter(|_| patterns.len() > 1) {
+                return ReportKind::SyntaxError
+                    .title("`_` already matches everything")
+                    .span(wildcard.span())
+                    .note("HINT: Give `_` an arm of its own")
+                    .as_err();
+            }
+
+            self.consume(TokenKind::FatArrowRight, "expected `=>` after the patterns")?;
+            let body = self.parse_branch(0)?;
+            let span = arm_start.extend(&self.get(-1).span);
+            arms.push(MatchArm { patterns, body, span, ranges: Vec::new() });
+
+            while self.current().kind == TokenKind::NewLine {
+                self.advance();
+            }
+            match self.current().kind {
+                TokenKind::Pipe => self.advance(),
+                TokenKind::RBrace => break,
+                _ =>
+                    return ReportKind::SyntaxError
+                        .title("Expected `|` between match arms")
+                        .span(self.current().span)
+                        .as_err(),
+            }
+        }
+
+        let span = start.extend(&self.current().span);
+        self.advance();
+
+        // the checker works out whether arms without a `_` cover everything
+        let is_exhaustive = arms.last().is_some_and(is_wildcard_arm);
+        Ok(ASTKind::Match(Box::new(value), arms, is_exhaustive).into_ast(span))
+    }
+
+    // _ | <const> | <const>..<const>
+    fn parse_pattern(&mut self) -> Result<Pattern> {
+        if self.current().kind == TokenKind::Underscore {
+            let span = self.current().span;
+            self.advance();
+            return Ok(Pattern::Wildcard(span));
+        }
+
+        let lo = self.parse_pattern_bound()?;
+        if self.current().kind != TokenKind::DotDot {
+            return Ok(Pattern::Value(lo));
+        }
+        self.advance();
+
+        Ok(Pattern::Range(Box::new(lo), Box::new(self.parse_pattern_bound()?)))
+    }
+
+    // Binds tighter than `=>` so the arrow ends the pattern, a leading `-` would swallow it otherwise
+    fn parse_pattern_bound(&mut self) -> Result<AST> {
+        if self.current().kind != TokenKind::Minus {
+            return self.parse_expression_bp(24);
+        }
+
+        let start = self.current().span;
+        self.advance();
+        let value = self.parse_expression_bp(24)?;
+        let span = start.extend(&value.span);
+        Ok(ASTKind::UnaryExpr(Operator::Negative, Box::new(value)).into_ast(span))
+    }
+
+    // break [<ident>] | continue [<ident>]
+    fn parse_loop_control(&mut self) -> AST {
+        let Token { kind, span, .. } = self.current();
+        self.advance();
+
+        let mut label = None;
+        let mut span = span;
+        if self.current().kind == TokenKind::Identifier {
+            label = Some(self.current().text.to_string());
+            span = span.extend(&self.current().span);
+            self.advance();
+        }
+
+        match kind {
+            TokenKind::KeywordBreak => ASTKind::Break(label),
+            _ => ASTKind::Continue(label),
+        }
+        .into_ast(span)
+    }
+
+    // extern <ident> (<ident> <type>, ...) [<type>]
+    fn parse_extern(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let Token { kind, span: name_span, text: name } = self.current();
+        if kind != TokenKind::Identifier {
+            return ReportKind::UnexpectedToken
+                .title("Expected the name of the external symbol")
+                .span(name_span)
+                .as_err();
+        }
+        self.advance();
+
+        let params = self.parse_parameters()?;
+        let ret = match self.is_return_type() {
+            true => {
+                let ret = self.parse_type()?;
+                self.advance();
+                Some(ret)
+            },
+            false => None,
+        };
+
+        Ok(ASTKind::ExternDeclaration(name.to_string(), params, ret)
+            .into_ast(start.extend(&self.get(-1).span)))
+    }
+
+    // op <operator> (<ident> <type>, ...) <type> { ... }
+    fn parse_operator(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        self.advance();
+
+        let Token { kind, span: op_span, text: symbol } = self.current();
+        self.advance();
+        let params = self.parse_parameters()?;
+
+        let op = match params.len() {
+            1 => Operator::from_prefix(kind)
+                .ok()
+                .filter(|op| matches!(op, Operator::Negative | Operator::Not)),
+            2 => Operator::from_infix(kind).ok().filter(|op| {
+                !matches!(
+                    op,
+                    Operator::Sequence
+                        | Operator::Assign
+                        | Operator::Thread
+                        | Operator::Cast
+                        | Operator::Access
+                        | Operator::AddressOf
+                )
+            }),
+            _ => None,
+        };
+        let Some(op) = op
+        else {
+            return ReportKind::SyntaxError
+                .title(format!("`{symbol}` can't be defined for {} operands", params.len()))
+                .span(op_span)
+                .note("HINT: Arithmetic, bitwise and comparison operators take two, `-` and `~` may take one")
+                .as_err();
+        };
+
+        if !Self::is_type_start(self.current().kind) {
+            return ReportKind::SyntaxError
+                .title("Expected the type the operator yields")
+                .span(self.current().span)
+                .as_err();
+        }
+        let ret = self.parse_type()?;
+        self.advance();
+
+        let body = self.parse_body()?;
+
+        let name = format!("op.{}.{}", format!("{op:?}").to_lowercase(), self.operators);
+        self.operators += 1;
+
+        Ok(ASTKind::OperatorDefinition(name, op, params, ret, Box::new(body))
+            .into_ast(start.extend(&op_span)))
+    }
+
+    // (<ident> <type>, ...)
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>> {
+        self.consume(TokenKind::LParen, "parameters go within parentheses")?;
+
+        let mut params = Vec::new();
+        while self.current().kind != TokenKind::RParen {
+            let Token { kind, span, text } = self.current();
+            if kind != TokenKind::Identifier {
+                return ReportKind::UnexpectedToken
+                    .title("Expected parameter name")
+                    .span(span)
+                    .as_err();
+            }
+            self.advance();
+
+            if !Self::is_type_start(self.current().kind) {
+                return ReportKind::SyntaxError
+                    .title(format!("Parameter '{text}' needs a type"))
+                    .span(span)
+                    .as_err();
+            }
+            let ty = self.parse_type()?;
+            let span = span.extend(&self.current().span);
+            self.advance();
+            params.push(Parameter { name: text.to_string(), ty, span });
+
+            match self.current().kind {
+                TokenKind::Comma => self.advance(),
+                TokenKind::RParen => {},
+                _ =>
+                    return ReportKind::SyntaxError
+                        .title("Expected comma between parameters")
+                        .span(self.current().span)
+                        .as_err(),
+            }
+        }
+        self.advance();
+
+        Ok(params)
+    }
+
+    // { <stmt>... }
+    fn parse_body(&mut self) -> Result<AST> {
+        self.consume(TokenKind::LBrace, "expected a block")?;
+        while self.current().kind == TokenKind::NewLine {
+            self.advance();
+        }
+        Ok(self.parse_block(false))
+    }
+
+    fn is_type_start(kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::DecimalIntLiteral
+                | TokenKind::LBrace
+                | TokenKind::LBracket
+                | TokenKind::Identifier
+        )
+    }
+
+    // ['] <ident> <type> [<- <expr>]
+    fn parse_variable_definition(&mut self) -> Result<AST> {
+        let start = self.current().span;
+        let is_mutable = self.current().kind == TokenKind::Apostrophe;
+        if is_mutable {
+            self.advance();
+        }
+
+        let name = self.current().text.to_string();
+        self.advance();
+
+        let ty = self.parse_type()?;
+        self.advance();
+
+        let value = match self.current().kind {
+            TokenKind::ArrowLeft => {
+                self.advance();
+                Some(Box::new(self.parse_expression()?))
+            },
+            _ => None,
+        };
+
+        let end = value.as_ref().map_or_else(|| self.get(-1).span, |value| value.span);
+        Ok(ASTKind::VariableDefinition(name, ty, is_mutable, value).into_ast(start.extend(&end)))
     }
 
     fn parse_interrupt(&mut self) -> Result<AST> {
@@ -198,52 +875,226 @@ impl<'contents> Parser<'contents> {
         }
     }
 
-    fn parse_label_attribute(&self) -> Option<LabelAttribute> {
-        match self.current().text {
-            "entry" => Some(LabelAttribute::Entry),
-            _ => None,
+    // Leaves the cursor on the last token of the attribute
+    fn parse_label_attribute(&mut self) -> Result<LabelAttribute> {
+        let Token { kind, span, text } = self.current();
+        match text {
+            "entry" => Ok(LabelAttribute::Entry),
+            "export" | "global" => Ok(LabelAttribute::Export),
+            "inline" => Ok(LabelAttribute::Inline),
+            "noreturn" => Ok(LabelAttribute::NoReturn),
+            "align" => {
+                self.advance();
+                let Token { kind, span, text } = self.current();
+                match text.parse::<usize>() {
+                    Ok(align)
+                        if kind == TokenKind::DecimalIntLiteral && align.is_power_of_two() =>
+                        Ok(LabelAttribute::Align(align)),
+                    _ => ReportKind::SyntaxError
+                        .title(format!("Invalid alignment '{text}'"))
+                        .span(span)
+                        .note("HINT: Alignments are a power of two in bytes, e.g. `align 16`")
+                        .as_err(),
+                }
+            },
+            "section" => {
+                self.advance();
+                let Token { kind, span, text } = self.current();
+                if kind != TokenKind::StringLiteral {
+                    return ReportKind::UnexpectedToken
+                        .title(format!("expected 'StringLiteral' got '{kind:?}'"))
+                        .span(span)
+                        .label("expected a section name, e.g. `section \".text.hot\"`")
+                        .as_err();
+                }
+                Ok(LabelAttribute::Section(text.to_string()))
+            },
+            _ => ReportKind::SyntaxError
+                .title(format!("Invalid attribute '{text}'"))
+                .span(span)
+                .note(if kind == TokenKind::Identifier {
+                    "HINT: Valid attributes are entry, export, inline, noreturn, align and section"
+                }
+                else {
+                    "HINT: Attributes go between the name and the `:` or `(`"
+                })
+                .as_err(),
         }
     }
 
-    fn parse_label(&mut self) -> Result<AST> {
-        if self.current().kind != TokenKind::Identifier {
-            return ReportKind::UnexpectedToken
-                .title("Expected Identifier")
-                .span(self.current().span)
-                .as_err();
+    fn parse_label_attributes(&mut self, until: TokenKind) -> Result<Vec<LabelAttribute>> {
+        let mut attributes: Vec<LabelAttribute> = Vec::new();
+        while self.current().kind != until {
+            let start = self.current().span;
+            let attribute = self.parse_label_attribute()?;
+
+            // `align 8 align 16` is as much of a duplicate as `inline inline`
+            if attributes.iter().any(|attr| attr.name() == attribute.name()) {
+                return ReportKind::DuplicateAttribute
+                    .title(format!("Duplicate attribute '{}'", attribute.name()))
+                    .span(start.extend(&self.current().span))
+                    .as_err();
+            }
+            attributes.push(attribute);
+            self.advance();
         }
+        Ok(attributes)
+    }
 
-        let mut attributes = Vec::with_capacity(5); // Could be adjusted
+    // A name and any attributes, then a colon, e.g. `loop align 16:`
+    fn is_label_definition(&self) -> bool {
+        let mut i = 1;
+        loop {
+            match self.peek(i).map(|token| token.kind) {
+                Some(TokenKind::Colon) => return true,
+                _ if self.is_attribute_token(i) => i += 1,
+                _ => return false,
+            }
+        }
+    }
 
-        let label = self.current().text;
+    // A name, any type parameters and attributes, then parameters, e.g. `main entry (`
+    fn is_function_definition(&self) -> bool {
+        let Some(mut i) = self.skip_type_parameters()
+        else {
+            return false;
+        };
+        loop {
+            match self.peek(i).map(|token| token.kind) {
+                Some(TokenKind::LParen) => return true,
+                _ if self.is_attribute_token(i) => i += 1,
+                _ => return false,
+            }
+        }
+    }
+
+    // The index just past `<T, U>` after a name, or of whatever follows the name without them
+    fn skip_type_parameters(&self) -> Option<isize> {
+        if self.peek(1)?.kind != TokenKind::LessThan {
+            return Some(1);
+        }
+
+        let mut i = 2;
+        loop {
+            if self.peek(i)?.kind != TokenKind::Identifier {
+                return None;
+            }
+            match self.peek(i + 1)?.kind {
+                TokenKind::Comma => i += 2,
+                TokenKind::GreaterThan => return Some(i + 2),
+                _ => return None,
+            }
+        }
+    }
+
+    // Numbers and strings only follow `align` and `section`, so `x 8:4` stays a variable
+    fn is_attribute_token(&self, index: isize) -> bool {
+        let previous = self.get(index - 1).text;
+        match self.peek(index).map(|token| token.kind) {
+            Some(TokenKind::Identifier) => true,
+            Some(TokenKind::DecimalIntLiteral) => previous == "align",
+            Some(TokenKind::StringLiteral) => previous == "section",
+            _ => false,
+        }
+    }
 
+    // A `{` is only a heap return type if the body's own `{` comes after it
+    fn is_return_type(&self) -> bool {
+        if self.current().kind != TokenKind::LBrace {
+            return Self::is_type_start(self.current().kind);
+        }
+
+        let mut depth = 0;
+        let mut i = 0;
+        loop {
+            match self.peek(i).map(|token| token.kind) {
+                Some(TokenKind::LBrace) => depth += 1,
+                Some(TokenKind::RBrace) => depth -= 1,
+                None | Some(TokenKind::EOF) => return false,
+                _ => {},
+            }
+            i += 1;
+
+            if depth == 0 {
+                return self.peek(i).is_some_and(|token| token.kind == TokenKind::LBrace);
+            }
+        }
+    }
+
+    // <ident> [<attribute>...] (<ident> <type>, ...) [<type>] { ... }
+    fn parse_function_definition(&mut self) -> Result<AST> {
+        let Token { span: start, text: name, .. } = self.current();
         self.advance();
-        while !matches!(self.current().kind, TokenKind::Colon | TokenKind::LBrace) {
-            match self.parse_label_attribute() {
-                Some(attribute) => {
-                    if attributes.contains(&attribute) {
-                        return ReportKind::DuplicateAttribute
-                            .title("Duplicate attribute encountered")
-                            .span(self.current().span)
-                            .as_err();
-                    };
 
-                    self.advance();
-                    attributes.push(attribute);
-                },
-                None => {
-                    self.advance();
+        let generics = self.parse_type_parameters()?;
+        let attributes = self.parse_label_attributes(TokenKind::LParen)?;
+        let params = self.parse_parameters()?;
+        let ret = match self.is_return_type() {
+            true => {
+                let ret = self.parse_type()?;
+                self.advance();
+                Some(ret)
+            },
+            false => None,
+        };
+
+        let span = start.extend(&self.get(-1).span);
+        let body = self.parse_body()?;
+        Ok(ASTKind::FunctionDefinition(
+            name.to_string(),
+            generics,
+            attributes,
+            params,
+            ret,
+            Box::new(body),
+        )
+        .into_ast(span))
+    }
+
+    // [<<ident>, ...>]
+    fn parse_type_parameters(&mut self) -> Result<Vec<TypeParameter>> {
+        let mut generics = Vec::new();
+        if self.current().kind != TokenKind::LessThan {
+            return Ok(generics);
+        }
+        self.advance();
+
+        loop {
+            let Token { kind, span, text } = self.current();
+            if kind != TokenKind::Identifier {
+                return ReportKind::UnexpectedToken
+                    .title("Expected the name of a type parameter")
+                    .span(span)
+                    .as_err();
+            }
+            generics.push(TypeParameter { name: text.to_string(), span });
+            self.advance();
+
+            match self.current().kind {
+                TokenKind::Comma => self.advance(),
+                TokenKind::GreaterThan => break,
+                _ =>
                     return ReportKind::SyntaxError
-                        .title("Invalid Label Attribute")
+                        .title("Type parameters left unclosed")
                         .span(self.current().span)
-                        .as_err();
-                },
+                        .as_err(),
             }
         }
         self.advance();
 
-        Ok(ASTKind::LabelDefinition(Some(label.to_string()), attributes)
-            .into_ast(self.current().span))
+        Ok(generics)
+    }
+
+    // <ident> [<attribute>...]:
+    fn parse_label(&mut self) -> Result<AST> {
+        let Token { span: start, text: label, .. } = self.current();
+        self.advance();
+
+        let attributes = self.parse_label_attributes(TokenKind::Colon)?;
+        let span = start.extend(&self.current().span);
+        self.advance();
+
+        Ok(ASTKind::LabelDefinition(Some(label.to_string()), attributes).into_ast(span))
     }
 
     fn parse_expression(&mut self) -> Result<AST> {
@@ -305,6 +1156,28 @@ impl<'contents> Parser<'contents> {
                 self.advance();
                 ret
             },
+            TokenKind::KeywordMatch => self.parse_match()?,
+            TokenKind::KeywordCast => {
+                let start = self.current().span;
+                self.advance();
+
+                let ty = self.parse_type()?;
+                self.advance();
+
+                let value = self.parse_expression_bp(18)?;
+                let span = start.extend(&value.span);
+                ASTKind::Cast(ty, Box::new(value), true).into_ast(span)
+            },
+            TokenKind::Dollar => {
+                let start = self.current().span;
+                self.advance();
+
+                let ty = self.parse_type()?;
+                let span = start.extend(&self.current().span);
+                self.advance();
+
+                ASTKind::SizeOf(ty).into_ast(span)
+            },
             // TODO: consider using if let in match. Currently its experimental
             tok => {
                 if let Ok(op) = Operator::from_prefix(tok) {
@@ -316,15 +1189,18 @@ impl<'contents> Parser<'contents> {
                         )
                     })?;
 
+                    let start = self.current().span;
                     self.advance();
 
                     let rhs = self.parse_expression_bp(r_bp)?;
 
                     match op {
-                        Operator::InternalCall => Ok(self.parse_function_call(rhs, false)?),
-                        Operator::ExternalCall => Ok(self.parse_function_call(rhs, true)?),
-                        _ =>
-                            Ok(ASTKind::UnaryExpr(op, Box::new(rhs)).into_ast(self.current().span)),
+                        Operator::InternalCall => Ok(self.parse_function_call(start, rhs, false)?),
+                        Operator::ExternalCall => Ok(self.parse_function_call(start, rhs, true)?),
+                        _ => {
+                            let span = start.extend(&rhs.span);
+                            Ok(ASTKind::UnaryExpr(op, Box::new(rhs)).into_ast(span))
+                        },
                     }
                 }
                 else {
@@ -350,10 +1226,15 @@ impl<'contents> Parser<'contents> {
                     break;
                 }
 
-                println!(" post {:?}", self.current());
+                if op == TokenKind::LBracket {
+                    lhs = self.parse_index(lhs)?;
+                    continue;
+                }
+
+                let span = lhs.span.extend(&self.current().span);
                 self.advance();
                 lhs = ASTKind::UnaryExpr(Operator::from_postfix(op).unwrap(), Box::new(lhs))
-                    .into_ast(self.current().span);
+                    .into_ast(span);
 
                 continue;
             }
@@ -363,25 +1244,49 @@ impl<'contents> Parser<'contents> {
                     break;
                 }
 
-                match op {
-                    TokenKind::ArrowLeft => {
-                        let t = self.parse_type()?;
-                        self.advance();
-                        lhs =
-                            ASTKind::TypeAnnotation(t, Box::new(lhs)).into_ast(self.current().span);
-                    },
-                    tok => {
-                        self.advance();
-                        let rhs = self.parse_expression_bp(r_bp)?;
-                        lhs = ASTKind::BinaryExpr(
-                            Operator::from_infix(tok).unwrap(),
-                            Box::new(lhs),
-                            Box::new(rhs),
-                        )
-                        .into_ast(self.current().span);
-                    },
+                self.advance();
+
+                if op == TokenKind::Question {
+                    let then = self.parse_branch(r_bp)?;
+                    let otherwise = match self.current().kind {
+                        TokenKind::Colon => {
+                            self.advance();
+                            Some(Box::new(self.parse_branch(r_bp)?))
+                        },
+                        _ => None,
+                    };
+
+                    let span = lhs.span.extend(&self.get(-1).span);
+                    lhs = ASTKind::Conditional(Box::new(lhs), Box::new(then), otherwise)
+                        .into_ast(span);
+                    continue;
+                }
+
+                // the right of `->` is a type rather than an expression
+                if op == TokenKind::ArrowRight {
+                    let ty = self.parse_type()?;
+                    let span = lhs.span.extend(&self.current().span);
+                    self.advance();
+
+                    lhs = ASTKind::Cast(ty, Box::new(lhs), false).into_ast(span);
+                    continue;
+                }
+
+                // as is the right of `.`, which names a field or position
+                if op == TokenKind::Dot {
+                    lhs = self.parse_access(lhs)?;
+                    continue;
                 }
 
+                let rhs = self.parse_expression_bp(r_bp)?;
+                let span = lhs.span.extend(&rhs.span);
+                lhs = ASTKind::BinaryExpr(
+                    Operator::from_infix(op).unwrap(),
+                    Box::new(lhs),
+                    Box::new(rhs),
+                )
+                .into_ast(span);
+
                 continue;
             }
 
@@ -391,8 +1296,103 @@ impl<'contents> Parser<'contents> {
         Ok(lhs)
     }
 
-    fn parse_function_call(&mut self, rhs: AST, is_external: bool) -> Result<AST> {
+    // <expr>.<ident> | <expr>.<int>, the checker decides whether a position is a field or an index
+    fn parse_access(&mut self, base: AST) -> Result<AST> {
+        let Token { kind, span, text } = self.current();
+        self.advance();
+
+        if kind == TokenKind::Identifier {
+            let field = ASTKind::Identifier(text.to_string()).into_ast(span);
+            let span = base.span.extend(&span);
+            return Ok(ASTKind::BinaryExpr(Operator::Access, Box::new(base), Box::new(field))
+                .into_ast(span));
+        }
+
+        if !matches!(kind, TokenKind::DecimalIntLiteral | TokenKind::FloatLiteral) {
+            return ReportKind::SyntaxError
+                .title("Expected a field name or position after `.`")
+                .span(span)
+                .as_err();
+        }
+
+        // `a.1.2` lexes as `a` `.` `1.2`
+        let mut base = base;
+        let mut offset = span.offset;
+        for part in text.split('.') {
+            let Ok(position) = part.parse::<usize>()
+            else {
+                return ReportKind::SyntaxError
+                    .title(format!("Invalid position `{part}`"))
+                    .span(span)
+                    .as_err();
+            };
+
+            let position_span = span.offset(offset).len(part.len());
+            offset += part.len() + 1;
+
+            let position = ASTKind::IntegerLiteral(position).into_ast(position_span);
+            let span = base.span.extend(&position_span);
+            base = ASTKind::BinaryExpr(Operator::Access, Box::new(base), Box::new(position))
+                .into_ast(span);
+        }
+        Ok(base)
+    }
+
+    // <expr>[<expr>] | <expr>[[<expr>]..[<expr>]]
+    fn parse_index(&mut self, base: AST) -> Result<AST> {
+        self.advance();
+        let lo = match self.current().kind {
+            TokenKind::DotDot => None,
+            _ => Some(Box::new(self.parse_expression_bp(0)?)),
+        };
+
+        let is_slice = self.current().kind == TokenKind::DotDot;
+        let hi = match is_slice {
+            true => {
+                self.advance();
+                match self.current().kind {
+                    TokenKind::RBracket => None,
+                    _ => Some(Box::new(self.parse_expression_bp(0)?)),
+                }
+            },
+            false => None,
+        };
+
+        if self.current().kind != TokenKind::RBracket {
+            return ReportKind::SyntaxError
+                .title("Index left unclosed")
+                .span(self.current().span)
+                .as_err();
+        }
+        let span = base.span.extend(&self.current().span);
+        self.advance();
+
+        Ok(match (is_slice, lo) {
+            (false, Some(index)) => ASTKind::BinaryExpr(Operator::Index, Box::new(base), index),
+            (_, lo) => ASTKind::Slice(Box::new(base), lo, hi),
+        }
+        .into_ast(span))
+    }
+
+    // Whether the token at `index` directly follows the one before it, `a[i]` indexes while
+    // `a [i]` is a dereference or a type
+    fn touches_previous(&self, index: isize) -> bool {
+        let (previous, token) = (self.get(index - 1).span, self.get(index).span);
+        previous.line_number == token.line_number
+            && previous.offset + previous.length == token.offset
+    }
+
+    // A `{` after `?` or `:` always opens a block, a heap literal has to be wrapped in parentheses
+    fn parse_branch(&mut self, min_bp: u8) -> Result<AST> {
+        match self.current().kind {
+            TokenKind::LBrace => self.parse_body(),
+            _ => self.parse_expression_bp(min_bp),
+        }
+    }
+
+    fn parse_function_call(&mut self, start: Span, rhs: AST, is_external: bool) -> Result<AST> {
         let mut t: Option<Type> = None;
+        let mut span = start.extend(&rhs.span);
 
         if self.current().kind == TokenKind::LessThan {
             self.advance();
@@ -404,8 +1404,8 @@ impl<'contents> Parser<'contents> {
                     .span(self.current().span)
                     .as_err();
             }
+            span = span.extend(&self.current().span);
             self.advance();
-            println!("fn: {}<{}>", rhs, t.as_ref().unwrap());
         }
 
         let mut args = Vec::new();
@@ -419,12 +1419,12 @@ impl<'contents> Parser<'contents> {
             | TokenKind::NewLine
             | TokenKind::RBracket
             | TokenKind::RParen
-            | TokenKind::Comma => {},
+            | TokenKind::Comma
+            | TokenKind::Dot => {},
             TokenKind::LParen => {
                 self.advance();
-                loop {
+                while self.current().kind != TokenKind::RParen {
                     args.push(self.parse_expression_bp(0)?);
-                    println!("arg {:?}", self.current().kind);
                     if self.current().kind == TokenKind::Comma {
                         self.advance();
                         continue;
@@ -437,30 +1437,29 @@ impl<'contents> Parser<'contents> {
                         .span(self.current().span)
                         .as_err();
                 }
+                span = span.extend(&self.current().span);
                 self.advance();
             },
             _ => {
-                args.push(self.parse_expression_bp(0)?);
+                let arg = self.parse_expression_bp(0)?;
+                span = span.extend(&arg.span);
+                args.push(arg);
             },
         }
 
-        if let Some(t) = t {
-            return Ok(ASTKind::TypeAnnotation(
-                t,
-                Box::new(
-                    ASTKind::Call(Box::new(rhs), args, is_external).into_ast(self.current().span),
-                ),
-            )
-            .into_ast(self.current().span));
+        let call = ASTKind::Call(Box::new(rhs), args, is_external).into_ast(span);
+        match t {
+            Some(t) => Ok(ASTKind::TypeAnnotation(t, Box::new(call)).into_ast(span)),
+            None => Ok(call),
         }
-
-        Ok(ASTKind::Call(Box::new(rhs), args, is_external).into_ast(self.current().span))
     }
 
     fn postfix_binding_power(&self) -> Option<(u8, ())> {
         match self.current().kind {
             TokenKind::PlusPlus | TokenKind::MinusMinus => Some((18, ())),
+            // NOTE: below calls so `!f [p]` still passes a dereference
             TokenKind::Apostrophe => Some((24, ())),
+            TokenKind::LBracket if self.touches_previous(0) => Some((24, ())),
             _ => None,
         }
     }
@@ -469,7 +1468,8 @@ impl<'contents> Parser<'contents> {
         match self.current().kind {
             TokenKind::Plus | TokenKind::Minus | TokenKind::Tilde => Some(((), 18)),
             TokenKind::Ampersand => Some(((), 20)),
-            TokenKind::At | TokenKind::Bang => Some(((), 22)),
+            // NOTE: binds tighter than `=>` so `!f => !g` threads rather than calling `f => !g`
+            TokenKind::At | TokenKind::Bang => Some(((), 25)),
             TokenKind::Apostrophe => Some(((), 24)),
             _ => None,
         }
@@ -477,8 +1477,10 @@ impl<'contents> Parser<'contents> {
 
     fn infix_binding_power(&self) -> Option<(u8, u8)> {
         match self.current().kind {
-            TokenKind::Equals => Some((2, 3)),
             TokenKind::Semicolon => Some((5, 4)),
+            // NOTE: below `||` so `a || b ? ..` tests both, but still within the right of `<-`
+            TokenKind::Question => Some((6, 6)),
+            TokenKind::ArrowLeft => Some((7, 6)),
             TokenKind::PipePipe => Some((6, 7)),
             TokenKind::CaretCaret => Some((8, 9)),
             TokenKind::AmpersandAmpersand => Some((10, 11)),
@@ -490,8 +1492,9 @@ impl<'contents> Parser<'contents> {
             TokenKind::ShiftLeft | TokenKind::ShiftRight => Some((16, 17)),
             TokenKind::Plus | TokenKind::Minus => Some((18, 19)),
             TokenKind::Star | TokenKind::Slash | TokenKind::Percent => Some((20, 21)),
-            TokenKind::ArrowLeft => Some((21, 22)),
             TokenKind::FatArrowRight | TokenKind::ArrowRight => Some((23, 24)),
+            // NOTE: level with indexing so `a.b[i].c` reads left to right
+            TokenKind::Dot => Some((24, 25)),
             _ => None,
         }
     }
@@ -517,7 +1520,12 @@ impl<'contents> Parser<'contents> {
                     _ => unreachable!(),
                 };
                 self.advance();
-                match usize::from_str_radix(text, base) {
+                // past the `0x`, `0b` or `0o`
+                let digits = match base {
+                    10 => text.replace('_', ""),
+                    _ => text[2..].replace('_', ""),
+                };
+                match usize::from_str_radix(&digits, base) {
                     Ok(val) => Ok(ASTKind::IntegerLiteral(val).into_ast(span)),
                     Err(_) =>
                         ReportKind::SyntaxError.title("Invalid Integer Literal").span(span).as_err(),
@@ -622,11 +1630,11 @@ impl<'contents> Parser<'contents> {
                         .title("Size cannot be zero")
                         .span(self.current().span)
                         .as_err();
-                };
+                }
 
                 Ok(Type::Size(size))
             },
-            TokenKind::Identifier => Ok(Type::Struct(self.current().text.to_string())),
+            TokenKind::Identifier => Ok(Type::Struct(self.current().text.to_string(), None)),
             TokenKind::LBrace | TokenKind::LBracket => {
                 let start_span = self.current().span;
                 let is_pointer = self.current().kind == TokenKind::LBracket;
@@ -656,7 +1664,7 @@ impl<'contents> Parser<'contents> {
                                     _ => unreachable!(),
                                 };
 
-                                if opposite != self.current().kind {return e};
+                                if opposite != self.current().kind {return e}
 
                                 ReportKind::SyntaxError
                                     .title("Incorrect heap nesting")
@@ -784,7 +1792,7 @@ impl<'contents> Parser<'contents> {
                             .span(self.current().span)
                             .note("HINT: Registers follow the format r<reg>. e.g r8 r32")
                             .as_err();
-                        };
+                        }
 
                     match self.current().text[1..].parse::<usize>() {
                         Err(e) => match e.kind() {
@@ -821,20 +1829,41 @@ impl<'contents> Parser<'contents> {
                 },
                 TokenKind::Colon => {
                     self.advance();
-                    self.advance();
 
-                    let elems: Option<usize> = if self.current().kind == TokenKind::DecimalIntLiteral {
-                        let elem_size = self.current().text.parse::<usize>().unwrap();
-                        if elem_size == 0 {
-                            return ReportKind::SyntaxError
-                                .title("Array size cannot be zero.")
-                                .note(format!("HINT: Did you mean [{t}:]"))
-                                .span(self.current().span)
-                                .as_err();
-                        }
-                        Some(elem_size)
-                    } else {
-                        None
+                    let elems = match self.get(1).kind {
+                        TokenKind::DecimalIntLiteral => {
+                            self.advance();
+                            let elem_size = self.current().text.parse::<usize>().unwrap();
+                            if elem_size == 0 {
+                                return ReportKind::SyntaxError
+                                    .title("Array size cannot be zero.")
+                                    .note(format!("HINT: Did you mean [{t}:]"))
+                                    .span(self.current().span)
+                                    .as_err();
+                            }
+                            Some(ArrayLength::Literal(elem_size))
+                        },
+                        // a named constant, evaluated by the checker
+                        TokenKind::Identifier => {
+                            self.advance();
+                            let Token { span, text, .. } = self.current();
+                            let expr = ASTKind::Identifier(text.to_string()).into_ast(span);
+                            Some(ArrayLength::Expr(Box::new(expr), None))
+                        },
+                        // NOTE: anything else has to be parenthesised, `<` and `>` would be ambiguous
+                        TokenKind::LParen => {
+                            self.advance();
+                            self.advance();
+                            let expr = self.parse_expression_bp(0)?;
+                            if self.current().kind != TokenKind::RParen {
+                                return ReportKind::SyntaxError
+                                    .title("Array length left unclosed")
+                                    .span(self.current().span)
+                                    .as_err();
+                            }
+                            Some(ArrayLength::Expr(Box::new(expr), None))
+                        },
+                        _ => None,
                     };
 
                     if self.peek(1).is_some_and(|t| t.kind == TokenKind::Semicolon) {
@@ -851,3 +1880,7 @@ impl<'contents> Parser<'contents> {
         })
     }
 }
+
+fn is_wildcard_arm(arm: &MatchArm) -> bool {
+    matches!(arm.patterns[..], [Pattern::Wildcard(_)])
+}
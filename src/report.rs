@@ -20,6 +20,21 @@ pub enum Level {
 pub enum ReportKind {
     _NOTE_,
     _WARNING_,
+    // Linter
+    UnusedBinding,
+    UnusedLabel,
+    UnusedValue,
+    NoEffectSequence,
+
+    // Checker
+    ConstantOverflow,
+    ShiftOverflow,
+    LossyCast,
+    UnreachablePattern,
+
+    // Control flow
+    UnreachableCode,
+
     _ERROR_,
     ArgumentParserError,
 
@@ -43,6 +58,63 @@ pub enum ReportKind {
     DuplicateAttribute,
     RegisterWithinHeap,
     MismatchedDelimeter,
+    DuplicateTag,
+
+    // Desugar
+    NotCallable,
+
+    // Checker
+    UndefinedVariable,
+    InvalidAssignmentTarget,
+    ImmutableMutation,
+    NonConstantExpression,
+    InvalidArrayLength,
+    DivisionByZero,
+    InvalidCast,
+    UndefinedType,
+    DuplicateType,
+    DuplicateField,
+    UnsizedField,
+    InvalidEnumType,
+    DuplicateVariant,
+    DuplicateDiscriminant,
+    InvalidDiscriminant,
+    AmbiguousVariant,
+    AliasCycle,
+    InvalidOperator,
+    DuplicateOperator,
+    UndefinedOperator,
+    InvalidDestructure,
+    DestructureArity,
+    DuplicateExtern,
+    UndeclaredExtern,
+    ArgumentCount,
+    DuplicateFunction,
+    InvalidAttribute,
+    DuplicateEntry,
+    ReturnFromNoReturn,
+    InvalidReturn,
+    InvalidCondition,
+    ControlOutsideLoop,
+    UndefinedLoop,
+    InvalidPattern,
+    NonExhaustiveMatch,
+    InvalidIndex,
+    IndexOutOfBounds,
+    InvalidAccess,
+    UnknownField,
+    InvalidGeneric,
+    UninferredGeneric,
+
+    // Control flow
+    MissingReturn,
+
+    // Codegen
+    UnknownSyscall,
+    InvalidRegister,
+    UnsupportedExpression,
+    ExceededInstanceLimit,
+    UnsupportedArch,
 
     // General
     IOError,
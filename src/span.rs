@@ -1,27 +1,32 @@
 use std::cmp::Ordering;
 use std::fmt::Formatter;
 
-#[derive(Clone, Copy, PartialEq)]
+use crate::scanner::Scanner;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Span {
     pub filename:    &'static str,
     pub line_number: usize,
     pub offset:      usize,
     pub length:      usize,
+    // line and offset just past the end, for spans that run onto later lines
+    pub end:         Option<(usize, usize)>,
 }
 
 impl Default for Span {
     fn default() -> Self {
-        Self { filename: "", line_number: 1, offset: 0, length: 0 }
+        Self { filename: "", line_number: 1, offset: 0, length: 0, end: None }
     }
 }
 
 impl Span {
     pub fn new(filename: &'static str, line_number: usize, offset: usize, length: usize) -> Self {
-        Self { filename, line_number, offset, length }
+        Self { filename, line_number, offset, length, end: None }
     }
 
     pub fn len(mut self, len: usize) -> Self {
         self.length = len;
+        self.end = None;
         self
     }
 
@@ -31,13 +36,26 @@ impl Span {
     }
 
     pub fn extend(mut self, other: &Self) -> Self {
-        self.len(
-            other.offset.checked_sub(self.offset).expect("other.offset behind self.offset!")
-                + other.length,
-        );
+        let (line_number, end) = other.end();
+        if line_number != self.line_number {
+            self.end = Some((line_number, end));
+            return self;
+        }
+
+        self.length = end.checked_sub(self.offset).expect("other.offset behind self.offset!");
         self
     }
 
+    // The line and offset just past the end
+    pub fn end(&self) -> (usize, usize) {
+        self.end.unwrap_or((self.line_number, self.offset + self.length))
+    }
+
+    pub fn contains(&self, other: &Self) -> bool {
+        let start = (self.line_number, self.offset);
+        start <= (other.line_number, other.offset) && other.end() <= self.end()
+    }
+
     pub fn ghost<T: std::fmt::Display>(self, ghost: T) -> (Self, HighVec) {
         let ghost = ghost.to_string();
         assert!(self.length > 0);
@@ -53,6 +71,17 @@ impl Span {
 
 impl std::fmt::Debug for Span {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some((line_number, end)) = self.end {
+            return write!(
+                f,
+                "{}:{}:{}-{line_number}:{}",
+                self.filename,
+                self.line_number,
+                self.offset,
+                end.saturating_sub(1)
+            );
+        }
+
         if self.length == 0 {
             return write!(f, "{}:{}:{}", self.filename, self.line_number, self.offset);
         }
@@ -68,9 +97,10 @@ impl std::fmt::Debug for Span {
     }
 }
 
+// Columns are counted from 1 like lines, as editors do
 impl std::fmt::Display for Span {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}:{}", self.filename, self.line_number, self.offset)
+        write!(f, "{}:{}:{}", self.filename, self.line_number, self.offset + 1)
     }
 }
 
@@ -80,8 +110,17 @@ impl From<Span> for (Span, HighVec) {
     fn from(val: Span) -> (Span, HighVec) {
         let mut vec = Vec::new();
 
+        // only the first line is shown, so a span running past it is highlighted to its end
+        let length = match val.end {
+            Some(_) => Scanner::get(val.filename)
+                .lines()
+                .nth(val.line_number - 1)
+                .map_or(0, |line| line.chars().count().saturating_sub(val.offset)),
+            None => val.length,
+        };
+
         (0..val.offset).for_each(|_| vec.push(HighlightKind::Empty));
-        (0..val.length).for_each(|_| vec.push(HighlightKind::Caret));
+        (0..length).for_each(|_| vec.push(HighlightKind::Caret));
 
         (val, vec)
     }
@@ -0,0 +1,124 @@
+use std::fmt::{Display, Formatter};
+
+// One routine's share of the stack, as laid out by codegen.
+pub struct Frame {
+    pub name:   String,
+    pub locals: usize,
+    // temporaries pushed while evaluating expressions
+    pub spills: usize,
+    // return address, saved rbp and any callee saved registers
+    pub saved:  usize,
+    pub calls:  Vec<String>,
+}
+
+impl Frame {
+    pub fn size(&self) -> usize {
+        self.locals + self.spills + self.saved
+    }
+}
+
+#[derive(Clone)]
+enum Depth {
+    Bounded(usize),
+    // calls something without a frame, e.g. an external function
+    AtLeast(usize, String),
+    // the cycle of calls responsible
+    Unbounded(Vec<String>),
+}
+
+impl Depth {
+    fn on_top_of(self, size: usize) -> Self {
+        match self {
+            Self::Bounded(depth) => Self::Bounded(depth + size),
+            Self::AtLeast(depth, callee) => Self::AtLeast(depth + size, callee),
+            unbounded @ Self::Unbounded(_) => unbounded,
+        }
+    }
+
+    fn worst(self, other: Self) -> Self {
+        match (self, other) {
+            (unbounded @ Self::Unbounded(_), _) | (_, unbounded @ Self::Unbounded(_)) => unbounded,
+            (Self::Bounded(a), Self::Bounded(b)) => Self::Bounded(a.max(b)),
+            (Self::AtLeast(a, callee), Self::Bounded(b) | Self::AtLeast(b, _))
+            | (Self::Bounded(a), Self::AtLeast(b, callee)) => Self::AtLeast(a.max(b), callee),
+        }
+    }
+}
+
+impl Display for Depth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bounded(depth) => write!(f, "{depth}"),
+            Self::AtLeast(depth, callee) => write!(f, "{depth}+ (calls unknown `{callee}`)"),
+            Self::Unbounded(cycle) => write!(f, "unbounded (recursion: {})", cycle.join(" -> ")),
+        }
+    }
+}
+
+// Worst case stack depth of every frame, following the call graph.
+pub struct StackUsage<'frames> {
+    frames: &'frames [Frame],
+    depths: Vec<Option<Depth>>,
+}
+
+impl<'frames> StackUsage<'frames> {
+    pub fn analyze(frames: &'frames [Frame]) -> Self {
+        let mut usage = Self { frames, depths: vec![None; frames.len()] };
+        for index in 0..frames.len() {
+            usage.depth(index, &mut Vec::new());
+        }
+        usage
+    }
+
+    fn depth(&mut self, index: usize, path: &mut Vec<usize>) -> Depth {
+        if let Some(depth) = &self.depths[index] {
+            return depth.clone();
+        }
+
+        if let Some(start) = path.iter().position(|i| *i == index) {
+            let cycle = path[start..].iter().chain([&index]);
+            return Depth::Unbounded(cycle.map(|i| self.frames[*i].name.clone()).collect());
+        }
+
+        path.push(index);
+        let frame = &self.frames[index];
+        let mut worst = Depth::Bounded(frame.size());
+
+        for callee in &frame.calls {
+            let depth = match self.frames.iter().position(|frame| frame.name == *callee) {
+                Some(callee) => self.depth(callee, path),
+                None => Depth::AtLeast(0, callee.clone()),
+            };
+            worst = worst.worst(depth.on_top_of(frame.size()));
+        }
+        path.pop();
+
+        self.depths[index] = Some(worst.clone());
+        worst
+    }
+}
+
+impl Display for StackUsage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self.frames.iter().map(|frame| frame.name.len()).max().unwrap_or(0).max(5);
+
+        writeln!(
+            f,
+            "{:width$}  {:>6}  {:>6}  {:>6}  {:>6}  Worst case",
+            "Label", "Frame", "Locals", "Spills", "Saved"
+        )?;
+        for (frame, depth) in self.frames.iter().zip(&self.depths) {
+            let depth = depth.as_ref().expect("stack depth not analyzed");
+            writeln!(
+                f,
+                "{:width$}  {:>6}  {:>6}  {:>6}  {:>6}  {depth}",
+                frame.name,
+                frame.size(),
+                frame.locals,
+                frame.spills,
+                frame.saved
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -18,6 +18,10 @@ pub enum TokenKind {
     KeywordOp,
     KeywordCast,
     KeywordExtern,
+    KeywordLoop,
+    KeywordBreak,
+    KeywordContinue,
+    KeywordMatch,
 
     FloatLiteral,
 
@@ -56,6 +60,7 @@ pub enum TokenKind {
     Colon,
     Comma,
     Dot,
+    DotDot,
     Slash,
     Question,
     ArrowLeft,
@@ -85,7 +90,7 @@ impl std::fmt::Display for Token<'_> {
         write!(f, "Token({:?}, {}", self.kind, format!("{:?}", self.span).bright_black())?;
         if !self.text.is_empty() {
             write!(f, ", {}", format!("{:?}", self.text).green())?;
-        };
+        }
         write!(f, ")")
     }
 }
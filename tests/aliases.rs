@@ -0,0 +1,41 @@
+mod common;
+use common::compile;
+
+#[test]
+fn alias_cycles_show_the_whole_chain() {
+    let compiled = compile("aliases_cycle", "type A B\ntype B C\ntype C A\nx A <- 1\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("AliasCycle"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("A -> B -> C -> A"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.lines().any(|line| line.contains("'B' is defined as `C` at ")
+            && line.ends_with("aliases_cycle.shd:2:1")),
+        "{}",
+        compiled.reports
+    );
+    assert!(
+        compiled.reports.lines().any(|line| line.contains("'C' is defined as `A` at ")
+            && line.ends_with("aliases_cycle.shd:3:1")),
+        "{}",
+        compiled.reports
+    );
+
+    let compiled = compile("aliases_self", "type A A\nret 0\n");
+    assert!(compiled.reports.contains("A -> A"), "{}", compiled.reports);
+}
+
+#[test]
+fn aliases_resolve_to_what_they_name() {
+    let compiled = compile("aliases_resolved", "type Word 4\ntype Half Word\nx Half <- 1\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("mov dword [rbp - 4], eax"), "{}", compiled.asm);
+
+    // diagnostics show both the alias and what it expands to
+    let compiled =
+        compile("aliases_expanded", "type Word 4\nx Word <- 1\ny {8, 8} <- x -> {8, 8}\nret 0\n");
+    assert!(
+        compiled.reports.contains("Cannot cast `Word (= 4)` to `{8, 8}`"),
+        "{}",
+        compiled.reports
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn constant_overflow() {
+    let compiled = compile("arithmetic_overflow", "x 1 <- 200 + 100\nret x\n");
+    assert!(compiled.reported("ConstantOverflow"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("1 byte value"), "{}", compiled.reports);
+}
+
+#[test]
+fn constants_within_range() {
+    let compiled = compile("arithmetic_in_range", "x 1 <- 100 + 100\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("ConstantOverflow"), "{}", compiled.reports);
+}
+
+#[test]
+fn wide_shifts() {
+    let compiled = compile("arithmetic_wide_shift", "x 1 <- 1 << 8\nret x\n");
+    assert!(compiled.reported("ShiftOverflow"), "{}", compiled.reports);
+}
+
+#[test]
+fn division_by_zero() {
+    let compiled = compile("arithmetic_division_by_zero", "x 8 <- 4 / 0\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("DivisionByZero"), "{}", compiled.reports);
+
+    let compiled = compile("arithmetic_modulo_by_zero", "x 8 <- 4 % 0\nret x\n");
+    assert!(compiled.reports.contains("Modulo by zero"), "{}", compiled.reports);
+}
+
+#[test]
+fn division_by_a_variable() {
+    let compiled = compile("arithmetic_variable_divisor", "'y 8 <- 0\nx 8 <- 4 / y\nret x\n");
+    assert!(!compiled.reported("DivisionByZero"), "{}", compiled.reports);
+}
+
+#[test]
+fn negating_the_smallest_constant() {
+    let compiled = compile("arithmetic_negate_wide_shift", "x 8 <- -(1 << 127)\nret x\n");
+    assert!(compiled.reported("ShiftOverflow"), "{}", compiled.reports);
+    assert!(!compiled.reports.contains("panicked"), "{}", compiled.reports);
+
+    // exactly -2^127, which has no positive counterpart
+    let compiled = compile(
+        "arithmetic_negate_min",
+        "x 8 <- 3\nret match x {\n  -((0 - (1 << 62)) * (1 << 62) * 8) => 1\n  | _ => 0\n}\n",
+    );
+    assert!(compiled.reported("ConstantOverflow"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("overflows while negating"), "{}", compiled.reports);
+}
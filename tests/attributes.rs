@@ -0,0 +1,50 @@
+mod common;
+use common::compile;
+
+#[test]
+fn exported_labels_are_global() {
+    let compiled = compile("attributes_export", "api export (a 8) 8 {\n  ret a\n}\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("global _start\nglobal api\n"), "{}", compiled.asm);
+    assert!(!compiled.reported("UnusedLabel"), "{}", compiled.reports);
+}
+
+#[test]
+fn inline_functions_are_expanded_where_called() {
+    let compiled =
+        compile("attributes_inline", "sq inline (a 8) 8 {\n  ret a * a\n}\nret !sq(3)\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    let start = compiled.routine("_start");
+    assert!(!start.contains(&"    call sq"), "{}", compiled.asm);
+    assert!(start.contains(&"    imul rax, rcx"), "{}", compiled.asm);
+    assert!(start.contains(&"sq.inline.1.end:"), "{}", compiled.asm);
+}
+
+#[test]
+fn noreturn_functions_trap_if_they_do_return() {
+    let compiled = compile("attributes_noreturn", "done noreturn () {\n  *exit 0\n}\n!done()\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert_eq!(compiled.routine("done").last(), Some(&"    ud2"), "{}", compiled.asm);
+
+    let compiled = compile("attributes_noreturn_ret", "done noreturn () {\n  ret\n}\n!done()\n");
+    assert!(compiled.reported("ReturnFromNoReturn"), "{}", compiled.reports);
+}
+
+#[test]
+fn aligned_labels_in_their_own_section() {
+    let compiled = compile(
+        "attributes_section",
+        "hot align 16 section \".text.hot\" (a 8) 8 {\n  ret a\n}\nret !hot(1)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("section .text.hot\n\nalign 16\nhot:\n"), "{}", compiled.asm);
+    // and back to where everything else goes
+    assert!(compiled.asm.ends_with("section .text\n"), "{}", compiled.asm);
+}
+
+#[test]
+fn unknown_attributes() {
+    let compiled = compile("attributes_unknown", "f fast () {\n}\n!f()\n");
+    assert!(!compiled.success);
+    assert!(compiled.reports.contains("Invalid attribute 'fast'"), "{}", compiled.reports);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn arrows_zero_extend() {
+    let compiled =
+        compile("casts_zero_extend", "x 8 <- cast 8 -1\ny 1 <- cast 1 x\nz 8 <- y -> 8\nret z\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("movzx eax, al"), "{}", compiled.asm);
+    assert!(!compiled.asm.contains("movsx"), "{}", compiled.asm);
+}
+
+#[test]
+fn casts_sign_extend() {
+    let compiled = compile(
+        "casts_sign_extend",
+        "x 8 <- cast 8 -1\ny 1 <- cast 1 x\nz 8 <- cast 8 y\nw 4 <- cast 4 x\nret z + (cast 8 w)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("movsx rax, al"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("movsxd rax, eax"), "{}", compiled.asm);
+
+    // narrowing has nothing to spread, it just drops the top
+    let compiled = compile("casts_sign_narrow", "x 8 <- 300\nret cast 1 x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.asm.contains("movsx"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("movzx eax, al"), "{}", compiled.asm);
+}
+
+#[test]
+fn division_is_unsigned() {
+    let compiled = compile("casts_unsigned_division", "x 8 <- cast 8 -2\nret x / 2\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("div rcx"), "{}", compiled.asm);
+    assert!(!compiled.asm.contains("idiv"), "{}", compiled.asm);
+}
+
+#[test]
+fn constants_extend_like_the_runtime() {
+    let compiled = compile(
+        "casts_constant_patterns",
+        "y 1 <- 255\nv 8 <- 3\nret match v {\n  (cast 8 y) => 1\n  | (y -> 8) => 2\n  | _ => 0\n}\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("mov rdx, 18446744073709551615"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("cmp rax, 255"), "{}", compiled.asm);
+}
@@ -0,0 +1,61 @@
+// not every test uses every helper
+#![allow(dead_code)]
+
+use std::process::Command;
+
+// What compiling a program produced
+pub struct Compiled {
+    pub success: bool,
+    // everything that was reported, without colors
+    pub reports: String,
+    // the generated assembly, empty if it never got that far
+    pub asm:     String,
+}
+
+impl Compiled {
+    // Whether a report of `kind` was made, e.g. `ConstantOverflow`
+    pub fn reported(&self, kind: &str) -> bool {
+        self.reports.contains(&format!("] {kind}:"))
+    }
+
+    // The lines of the routine called `label`, up to the next blank line
+    pub fn routine(&self, label: &str) -> Vec<&str> {
+        self.asm
+            .lines()
+            .skip_while(|line| *line != format!("{label}:"))
+            .take_while(|line| !line.is_empty())
+            .collect()
+    }
+}
+
+// Compiles `source` as `<name>.shd`, names have to be unique across every test
+pub fn compile(name: &str, source: &str) -> Compiled {
+    compile_with(name, source, &[])
+}
+
+// Same as `compile`, passing `args` to the compiler as well, e.g. `-d`
+pub fn compile_with(name: &str, source: &str, args: &[&str]) -> Compiled {
+    let dir = std::env::temp_dir().join("sharc-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join(format!("{name}.shd"));
+    let output = dir.join(format!("{name}.asm"));
+    std::fs::write(&file, source).unwrap();
+    let _ = std::fs::remove_file(&output);
+
+    let result = Command::new(env!("CARGO_BIN_EXE_sharc"))
+        .arg("-f")
+        .arg(&file)
+        .arg("-o")
+        .arg(&output)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run sharc");
+
+    Compiled {
+        success: result.status.success(),
+        reports: String::from_utf8_lossy(&result.stdout).into_owned()
+            + &String::from_utf8_lossy(&result.stderr),
+        asm:     std::fs::read_to_string(&output).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+use common::compile;
+
+#[test]
+fn without_an_else_the_branch_is_skipped() {
+    let compiled = compile("conditionals_no_else", "'x 8 <- 1\nc 1 <- 1\nc ? { x <- 2 }\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    let start = compiled.routine("_start");
+    let test = start.iter().position(|line| *line == "    test rax, rax").unwrap();
+    assert_eq!(start[test..test + 5], [
+        "    test rax, rax",
+        "    jz if.1.end",
+        "    mov rax, 2",
+        "    mov qword [rbp - 8], rax",
+        "if.1.end:",
+    ]);
+    assert!(!compiled.asm.contains("if.1.else"), "{}", compiled.asm);
+}
+
+#[test]
+fn with_an_else_one_branch_jumps_over_the_other() {
+    let compiled = compile("conditionals_else", "c 1 <- 1\ny 8 <- c ? { 1 } : { 2 }\nret y\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(
+        compiled.asm.contains("    jz if.1.else\n    mov rax, 1\n    jmp if.1.end\nif.1.else:\n"),
+        "{}",
+        compiled.asm
+    );
+}
+
+#[test]
+fn conditions_have_to_fit_a_register() {
+    let compiled = compile("conditionals_wide", "'x 8 <- 1\nc 16 <- 1\nc ? { x <- 2 }\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("InvalidCondition"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("16 bytes don't fit in a register"), "{}", compiled.reports);
+}
@@ -0,0 +1,36 @@
+mod common;
+use common::compile;
+
+#[test]
+fn every_element_needs_exactly_one_name() {
+    let compiled = compile("destructuring_too_many", "'p {8, 8}\ndestr { x, y, z } <- p\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("DestructureArity"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("Destructuring 2 elements into 3 names"),
+        "{}",
+        compiled.reports
+    );
+
+    let compiled = compile("destructuring_too_few", "'p {8, 8}\ndestr { x } <- p\nret x\n");
+    assert!(
+        compiled.reports.contains("Destructuring 2 elements into 1 name\n"),
+        "{}",
+        compiled.reports
+    );
+
+    let compiled = compile("destructuring_literal", "destr { x, y } <- {1, 2, 3}\nret x + y\n");
+    assert!(compiled.reports.contains("this heap has 3 elements"), "{}", compiled.reports);
+}
+
+#[test]
+fn structs_destructure_field_by_field() {
+    let compiled = compile(
+        "destructuring_struct",
+        "struct P { a 8, b 4 }\n'p P\ndestr { x, y } <- p\nret x + y\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    // each binding takes its field's size
+    assert!(compiled.asm.contains("mov rax, qword [rcx + 0]"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("mov eax, dword [rcx + 8]"), "{}", compiled.asm);
+}
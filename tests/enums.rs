@@ -0,0 +1,39 @@
+mod common;
+
+use common::compile;
+
+const COLORS: &str = "enum Light 1 { Red, Green }\nenum Paint 1 { Green <- 4, Blue }\n";
+
+#[test]
+fn enums_may_share_variant_names() {
+    let compiled = compile(
+        "enums_shared_names",
+        &format!("{COLORS}x Light <- Green\ny Paint <- Green\nret x + y\n"),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("mov rax, 1\n"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("mov rax, 4\n"), "{}", compiled.asm);
+}
+
+#[test]
+fn matches_use_the_matched_enum() {
+    let compiled = compile(
+        "enums_match_scope",
+        &format!("{COLORS}x Paint <- Blue\nret match x {{\n  Green => 1\n}}\n"),
+    );
+    assert!(compiled.reported("NonExhaustiveMatch"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("doesn't cover 'Blue'"), "{}", compiled.reports);
+}
+
+#[test]
+fn ambiguous_variants() {
+    let compiled = compile("enums_ambiguous", &format!("{COLORS}ret Green\n"));
+    assert!(!compiled.success);
+    assert!(compiled.reported("AmbiguousVariant"), "{}", compiled.reports);
+}
+
+#[test]
+fn duplicate_variants() {
+    let compiled = compile("enums_duplicate", "enum Light 1 { Red, Red }\n");
+    assert!(compiled.reported("DuplicateVariant"), "{}", compiled.reports);
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn external_calls_align_the_stack() {
+    let compiled =
+        compile("externs_alignment", "extern abs(a 8) 8\n'x 8 <- 1\nx <- x + @abs(3)\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let asm = compiled.asm.lines().map(str::trim).collect::<Vec<_>>();
+    let call = asm.iter().position(|line| *line == "call abs").expect("no call to abs");
+    assert_eq!(asm[call - 5..call - 1], ["mov rax, rsp", "and rsp, -16", "push rax", "push rax"]);
+    assert_eq!(asm[call + 1], "mov rsp, [rsp]");
+}
+
+#[test]
+fn narrow_external_returns_are_extended() {
+    let compiled = compile(
+        "externs_narrow_return",
+        "extern getchar() 4\nextern labs(a 8) 8\nx 8 <- @getchar()\ny 8 <- @labs(x)\nret y\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let asm = compiled.asm.lines().map(str::trim).collect::<Vec<_>>();
+    let after = |name: &str| asm[asm.iter().position(|line| *line == name).unwrap() + 2];
+    assert_eq!(after("call getchar"), "mov eax, eax");
+    assert_ne!(after("call labs"), "mov eax, eax");
+}
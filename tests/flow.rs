@@ -0,0 +1,99 @@
+mod common;
+use common::{compile, compile_with};
+
+#[test]
+fn conditionals_branch_around_a_return() {
+    let compiled =
+        compile_with("flow_graph", "f (c 1) 8 {\n  c ? { ret 1 }\n  ret 2\n}\nret !f(1)\n", &[
+            "-d",
+        ]);
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains(concat!(
+            "(ControlFlowGraph: f\n",
+            "  bb0: 1 statements -> Branch(1, 3)\n",
+            "  bb1: 1 statements -> Return(true)\n",
+        )),
+        "{}",
+        compiled.reports
+    );
+}
+
+#[test]
+fn code_after_a_return_is_unreachable() {
+    let compiled =
+        compile("flow_unreachable", "f () 8 {\n  ret 1\n  x 8 <- 2\n  ret x\n}\nret !f()\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.reported("UnreachableCode"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("this and the following 1 statements"),
+        "{}",
+        compiled.reports
+    );
+
+    // a dead conditional is one report, not one for every branch inside it
+    let compiled = compile(
+        "flow_unreachable_branches",
+        "f (c 1) 8 {\n  ret 1\n  c ? { ret 2 } : { ret 3 }\n}\nret !f(1)\n",
+    );
+    assert_eq!(compiled.reports.matches("] UnreachableCode:").count(), 1, "{}", compiled.reports);
+    assert!(compiled.reports.contains("this statement is never run"), "{}", compiled.reports);
+}
+
+#[test]
+fn falling_off_the_end_of_a_valued_function() {
+    let compiled = compile("flow_falls_off", "f () 8 {\n}\nret !f()\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("MissingReturn"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("flow_falls_off.shd:2:1\n"), "{}", compiled.reports);
+
+    // only one branch returns
+    let compiled = compile("flow_one_branch", "f (c 1) 8 {\n  c ? { ret 1 }\n}\nret !f(1)\n");
+    assert!(compiled.reported("MissingReturn"), "{}", compiled.reports);
+
+    // nothing to return, nothing missing
+    let compiled = compile("flow_no_value", "f () {\n}\n!f()\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("MissingReturn"), "{}", compiled.reports);
+}
+
+#[test]
+fn every_path_returns_a_value() {
+    let compiled = compile(
+        "flow_all_paths",
+        concat!(
+            "f (c 1) 8 {\n  c ? { ret 1 } : { ret 2 }\n}\n",
+            "g (c 1) 8 {\n  match c {\n    0 => { ret 1 }\n    | _ => { ret 2 }\n  }\n}\n",
+            "h () 8 {\n  loop {\n  }\n}\n",
+            "ret !f(1) + !g(0)\n",
+        ),
+    );
+    assert!(!compiled.reported("MissingReturn"), "{}", compiled.reports);
+}
+
+#[test]
+fn returns_inside_an_expression() {
+    // the only return is inside the arm, the rest of the function falls off the end
+    let compiled = compile(
+        "flow_nested_missing",
+        "f (c 1) 8 {\n  x 8 <- match c {\n    0 => { ret 1 }\n    | _ => 2\n  }\n}\nret !f(0)\n",
+    );
+    assert!(compiled.reported("MissingReturn"), "{}", compiled.reports);
+
+    // and it doesn't hide what comes after it
+    let compiled = compile(
+        "flow_nested_return",
+        "f (c 1) 8 {\n  x 8 <- match c {\n    0 => { ret 1 }\n    | _ => 2\n  }\n  ret x\n}\nret !f(0)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("MissingReturn"), "{}", compiled.reports);
+    assert!(!compiled.reported("UnreachableCode"), "{}", compiled.reports);
+
+    // both branches return, so nothing after them runs
+    let compiled = compile(
+        "flow_nested_both",
+        "f (c 1) 8 {\n  x 8 <- c ? { ret 1 } : { ret 2 }\n  ret x\n}\nret !f(0)\n",
+    );
+    assert!(compiled.reported("UnreachableCode"), "{}", compiled.reports);
+    assert!(!compiled.reported("MissingReturn"), "{}", compiled.reports);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::compile;
+
+const ID: &str = "id<T> (x T) T {\n  ret x\n}\n";
+
+#[test]
+fn inferred_from_what_the_call_is_stored_into() {
+    let compiled = compile("generics_expected", &format!("{ID}a 1 <- !id(3)\nret a\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("call id.0"), "{}", compiled.asm);
+}
+
+#[test]
+fn uninferred_parameters() {
+    let compiled = compile("generics_uninferred", &format!("{ID}ret !id(3)\n"));
+    assert!(compiled.reported("UninferredGeneric"), "{}", compiled.reports);
+    // both locations count columns from 1
+    assert!(compiled.reports.contains("generics_uninferred.shd:4:5\n"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("defined at"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("generics_uninferred.shd:1:1\n"), "{}", compiled.reports);
+}
+
+const MAX: &str = "max<T> (a T, b T) T {\n  ret a > b ? a : b\n}\n";
+
+#[test]
+fn one_instance_per_set_of_types() {
+    let compiled = compile(
+        "generics_instances",
+        &format!(
+            "{MAX}{}",
+            concat!(
+                "x 1 <- 3\ny 4 <- 7\n",
+                "z 1 <- !max(x, 2)\nw 4 <- !max(y, 5)\nv 1 <- !max(z, 6)\n",
+                "ret !max<8>(v, w)\n",
+            )
+        ),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let calls = compiled
+        .asm
+        .lines()
+        .filter(|line| line.trim().starts_with("call max."))
+        .collect::<Vec<_>>();
+    assert_eq!(calls, ["    call max.0", "    call max.1", "    call max.0", "    call max.2"]);
+    assert_eq!(compiled.asm.matches("\nmax.0:\n").count(), 1, "{}", compiled.asm);
+    assert!(!compiled.asm.contains("\nmax:\n"), "{}", compiled.asm);
+}
+
+#[test]
+fn instances_substitute_their_types() {
+    let compiled = compile(
+        "generics_substitution",
+        &format!("{MAX}x 1 <- 3\ny 4 <- 7\nz 1 <- !max(x, 2)\nret !max(y, 5)\n"),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let byte = compiled.routine("max.0");
+    assert!(byte.contains(&"    mov byte [rbp - 1], dil"), "{byte:#?}");
+    assert!(byte.contains(&"    movzx eax, byte [rbp - 1]"), "{byte:#?}");
+
+    let dword = compiled.routine("max.1");
+    assert!(dword.contains(&"    mov dword [rbp - 4], edi"), "{dword:#?}");
+    assert!(dword.contains(&"    mov eax, dword [rbp - 4]"), "{dword:#?}");
+}
@@ -0,0 +1,62 @@
+mod common;
+use common::compile;
+
+#[test]
+fn indices_are_scaled_by_the_element_size() {
+    let compiled = compile("indexing_scaled", "'a 4:4\n'i 8 <- 2\na[i] <- 7\nret a[i]\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("lea rax, [rcx + rax * 4]"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("mov dword [rcx], eax"), "{}", compiled.asm);
+}
+
+#[test]
+fn constant_indices_past_the_end() {
+    let compiled = compile("indexing_out_of_bounds", "'a 8:4\nret a[4]\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("IndexOutOfBounds"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("there are 4 elements"), "{}", compiled.reports);
+
+    let compiled = compile("indexing_last", "'a 8:4\nret a[3]\n");
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // a slice may end right after the last element but no further, nor before it starts
+    let compiled = compile("indexing_slice_end", "'a 8:4\ndestr { p, n } <- a[4..5]\nret n\n");
+    assert!(compiled.reported("IndexOutOfBounds"), "{}", compiled.reports);
+    let compiled =
+        compile("indexing_slice_backwards", "'a 8:4\ndestr { p, n } <- a[3..1]\nret n\n");
+    assert!(
+        compiled.reports.contains("Slice `3..1` ends before it starts"),
+        "{}",
+        compiled.reports
+    );
+}
+
+#[test]
+fn slices_are_a_pointer_and_a_length() {
+    // constant bounds fold the length
+    let compiled =
+        compile("indexing_slice_constant", "'a 8:4\ndestr { p, n } <- a[1..3]\nret [p] + n\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("add rax, 8\n    mov qword [rbp - 40], rax"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("mov rax, 2\n    mov qword [rbp - 48], rax"), "{}", compiled.asm);
+
+    // the end defaults to the array's length
+    let compiled = compile(
+        "indexing_slice_open",
+        "'a 8:4\n'i 8 <- 1\ndestr { p, n } <- a[i..]\nret [p] + n\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("mov rax, 4\n    push rax"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("sub rax, rcx"), "{}", compiled.asm);
+}
+
+#[test]
+fn slice_starts_are_evaluated_once() {
+    let compiled = compile(
+        "indexing_slice_effects",
+        "'a 8:4\n'i 8 <- 0\ndestr { p, n } <- a[i++..3]\nret [p] + n\n",
+    );
+    assert!(!compiled.success);
+    assert!(compiled.reported("InvalidIndex"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("evaluated twice"), "{}", compiled.reports);
+}
@@ -0,0 +1,84 @@
+mod common;
+use common::{compile, compile_with};
+
+#[test]
+fn bindings_that_are_never_read() {
+    let compiled = compile("lints_unused_binding", "'x 8\n'y 8 <- 2\ny <- 3\nz 8 <- 4\nret z\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.reports.contains("Unused binding 'x'"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("Binding 'y' is written to but never read"),
+        "{}",
+        compiled.reports
+    );
+    assert!(!compiled.reports.contains("'z'"), "{}", compiled.reports);
+
+    // parameters count too
+    let compiled =
+        compile("lints_unused_parameter", "f (a 8, b 8) 8 {\n  ret a\n}\nret !f(1, 2)\n");
+    assert!(compiled.reports.contains("Unused binding 'b'"), "{}", compiled.reports);
+}
+
+#[test]
+fn labels_that_are_never_used() {
+    let compiled = compile("lints_unused_label", "unused:\nused:\nx 8 <- used\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.reports.contains("Label 'unused' is never used"), "{}", compiled.reports);
+    assert!(!compiled.reports.contains("'used'"), "{}", compiled.reports);
+
+    // called from outside the program
+    let compiled = compile("lints_exported_label", "outside export:\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("UnusedLabel"), "{}", compiled.reports);
+}
+
+#[test]
+fn values_that_are_thrown_away() {
+    let compiled = compile("lints_discarded", "2 + 3\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.reported("UnusedValue"), "{}", compiled.reports);
+
+    // a branch's value is only thrown away if the whole conditional's is
+    let compiled = compile("lints_discarded_branch", "c 1 <- 1\nc ? { 1 }\nret 0\n");
+    assert!(compiled.reported("UnusedValue"), "{}", compiled.reports);
+    let compiled = compile("lints_yielded_branch", "c 1 <- 1\nx 8 <- c ? { 1 } : { 2 }\nret x\n");
+    assert!(!compiled.reported("UnusedValue"), "{}", compiled.reports);
+}
+
+#[test]
+fn error_levels_limit_what_is_shown() {
+    let source = "x 8 <- 1\nret 0\n";
+    let compiled = compile_with("lints_level_warn", source, &["-l", "warn"]);
+    assert!(compiled.reported("UnusedBinding"), "{}", compiled.reports);
+
+    let compiled = compile_with("lints_level_error", source, &["-l", "error"]);
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("UnusedBinding"), "{}", compiled.reports);
+
+    // silent shows nothing, errors still fail the build
+    let compiled = compile_with("lints_level_silent", "ret y\n", &["-l", "silent"]);
+    assert!(!compiled.success);
+    assert!(!compiled.reported("UndefinedVariable"), "{}", compiled.reports);
+
+    let compiled = compile_with("lints_level_fatal", "ret y\n", &["-l", "fatal"]);
+    assert!(!compiled.reported("UndefinedVariable"), "{}", compiled.reports);
+    let compiled = compile_with("lints_level_error_shown", "ret y\n", &["-l", "error"]);
+    assert!(compiled.reported("UndefinedVariable"), "{}", compiled.reports);
+}
+
+#[test]
+fn sequences_run_their_left_side_for_its_effects() {
+    let compiled = compile("lints_sequence_no_effect", "'x 8 <- 1\ny 8 <- (1; x)\nret y\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.reported("NoEffectSequence"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("| y 8 <- (1; x)\n  |         ^ this value"),
+        "{}",
+        compiled.reports
+    );
+
+    let compiled = compile("lints_sequence_effect", "'x 8 <- 1\ny 8 <- (x++; x)\nret y\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("NoEffectSequence"), "{}", compiled.reports);
+    assert!(compiled.asm.contains("inc rax"), "{}", compiled.asm);
+}
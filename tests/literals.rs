@@ -0,0 +1,24 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn every_digit_of_each_base() {
+    for (name, literal, value) in [
+        ("literals_decimal", "1_234_567_890", "1234567890"),
+        ("literals_binary", "0b1011", "11"),
+        ("literals_octal", "0o17", "15"),
+        ("literals_hexadecimal", "0xFf", "255"),
+    ] {
+        let compiled = compile(name, &format!("ret {literal}\n"));
+        assert!(compiled.success, "{literal}: {}", compiled.reports);
+        assert!(compiled.asm.contains(&format!("mov rax, {value}\n")), "{}", compiled.asm);
+    }
+}
+
+#[test]
+fn invalid_digits() {
+    let compiled = compile("literals_invalid_digit", "ret 0b12\n");
+    assert!(!compiled.success);
+    assert!(compiled.reports.contains("'2' not valid for base2"), "{}", compiled.reports);
+}
@@ -0,0 +1,73 @@
+mod common;
+use common::compile;
+
+#[test]
+fn returns_from_within_an_expression_reset_the_stack() {
+    let compiled = compile(
+        "loops_nested_return",
+        "f (c 8) 8 {\n  'k 8;r1 <- 5\n  loop {\n    k <- k + (c = 0 ? { ret k } : { 1 })\n  }\n}\nret !f(0)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // `k` is still pushed when the return jumps out, so rsp has to be put back under r12
+    let routine = compiled.routine("f");
+    let end = routine.iter().position(|line| *line == "f.end:").unwrap();
+    assert_eq!(routine[end..], [
+        "f.end:",
+        "    lea rsp, [rbp - 24]",
+        "    pop r12",
+        "    leave",
+        "    ret",
+    ]);
+    assert_eq!(routine[3..5], ["    sub rsp, 16", "    push r12"]);
+}
+
+#[test]
+fn returns_from_within_an_inline_expression() {
+    let compiled = compile(
+        "loops_inline_return",
+        "f inline (c 8) 8 {\n  ret 1 + (c = 0 ? { ret 7 } : { 1 })\n}\nret 2 + !f(0)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // the `1` is dropped, the caller's `2` is left for it to pop
+    let start = compiled.routine("_start");
+    let ret = start.iter().position(|line| *line == "    mov rax, 7").unwrap();
+    assert_eq!(start[ret + 1..ret + 3], ["    add rsp, 8", "    jmp f.inline.1.end"]);
+}
+
+#[test]
+fn labels_pick_which_loop_to_leave() {
+    let compiled = compile(
+        "loops_labelled",
+        concat!(
+            "'i 8 <- 0\nloop outer: i < 10 {\n  'j 8 <- 0\n  loop {\n    j++\n",
+            "    j = 3 ? { continue outer }\n    i = 5 ? { break outer }\n    i++\n  }\n}\nret i\n",
+        ),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("    jne if.3.end\n    jmp loop.1\n"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("    jne if.4.end\n    jmp loop.1.end\n"), "{}", compiled.asm);
+    // the inner loop only goes around itself
+    assert!(
+        compiled.asm.contains("    jmp loop.2\nloop.2.end:\n    jmp loop.1\n"),
+        "{}",
+        compiled.asm
+    );
+}
+
+#[test]
+fn leaving_loops_that_are_not_there() {
+    let compiled = compile("loops_unknown_label", "loop {\n  break nowhere\n}\nret 0\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UndefinedLoop"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("No loop named 'nowhere'"), "{}", compiled.reports);
+
+    let compiled = compile("loops_outside", "continue\nret 0\n");
+    assert!(compiled.reported("ControlOutsideLoop"), "{}", compiled.reports);
+
+    // a function can't leave the loop it's defined in
+    let compiled =
+        compile("loops_through_function", "loop {\n  f () {\n    break\n  }\n  break\n}\nret 0\n");
+    assert!(compiled.reported("ControlOutsideLoop"), "{}", compiled.reports);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::compile;
+
+const DIRECTION: &str = "enum Direction 1 { North, East, South, West }\n";
+
+#[test]
+fn missing_variants() {
+    let compiled = compile(
+        "matches_missing_variants",
+        &format!(
+            "{DIRECTION}d Direction <- East\nret match d {{\n  North => 1\n  | East => 2\n}}\n"
+        ),
+    );
+    assert!(!compiled.success);
+    assert!(compiled.reported("NonExhaustiveMatch"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("doesn't cover 'South', 'West'"), "{}", compiled.reports);
+}
+
+#[test]
+fn every_variant_is_exhaustive() {
+    let compiled = compile(
+        "matches_every_variant",
+        &format!(
+            "{DIRECTION}d Direction <- East\nret match d {{\n  North, South => 1\n  | East, West => 2\n}}\n"
+        ),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+}
+
+#[test]
+fn ranges_covering_every_value_are_exhaustive() {
+    let compiled = compile(
+        "matches_full_range",
+        "x 1 <- 3\nret match x {\n  0..100 => 1\n  | 101..255 => 2\n}\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.reported("NonExhaustiveMatch"), "{}", compiled.reports);
+}
+
+#[test]
+fn unreachable_patterns() {
+    let compiled = compile(
+        "matches_unreachable",
+        "x 1 <- 3\nret match x {\n  0..10 => 1\n  | 5 => 2\n  | _ => 3\n}\n",
+    );
+    assert!(compiled.reported("UnreachablePattern"), "{}", compiled.reports);
+}
+
+#[test]
+fn dense_matches_jump_through_a_table() {
+    let compiled = compile(
+        "matches_jump_table",
+        "x 8 <- 2\nret match x {\n  0 => 10\n  | 1 => 11\n  | 2 => 12\n  | 3 => 13\n  | _ => 0\n}\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("match.1.table: dq"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("jmp [match.1.table + rcx * 8]"), "{}", compiled.asm);
+    assert!(!compiled.asm.contains("je match.1.arm"), "{}", compiled.asm);
+}
+
+#[test]
+fn sparse_matches_compare_in_turn() {
+    let compiled = compile(
+        "matches_compare_chain",
+        "x 8 <- 2\nret match x {\n  0 => 10\n  | 100 => 11\n  | 200 => 12\n  | 300 => 13\n  | _ => 0\n}\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.asm.contains(".table"), "{}", compiled.asm);
+    for (arm, value) in [(0, 0), (1, 100), (2, 200), (3, 300)] {
+        let compare = format!("cmp rax, {value}\n    je match.1.arm.{arm}");
+        assert!(compiled.asm.contains(&compare), "{}", compiled.asm);
+    }
+}
+
+#[test]
+fn patterns_at_the_top_of_the_range() {
+    // -1 is u64::MAX, a table from 0 up to it would never fit
+    let compiled = compile(
+        "matches_wrapping_span",
+        "x 8 <- 3\nret match x { 0 => 1 | 1 => 2 | 2 => 3 | -1 => 4 }\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.asm.contains(".table"), "{}", compiled.asm);
+    assert!(compiled.asm.contains("mov rdx, 18446744073709551615"), "{}", compiled.asm);
+
+    // dense right below u64::MAX, so it still gets a table
+    let compiled = compile(
+        "matches_top_table",
+        concat!(
+            "x 8 <- 3\nret match x {\n",
+            "  0xFFFFFFFFFFFFFFFF => 1\n  | -2 => 2\n  | -3 => 3\n  | -4 => 4\n  | _ => 0\n}\n",
+        ),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("match.1.table: dq"), "{}", compiled.asm);
+}
+
+#[test]
+fn matches_span_every_line_of_their_arms() {
+    let compiled =
+        compile("matches_multi_line", "ret 1\nx 8 <- match 1 {\n  0 => 1\n  | _ => 2\n}\n");
+    assert!(compiled.reported("UnreachableCode"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("| ^^^^^^^^^^^^^^^^ this statement"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("runs through line 5"), "{}", compiled.reports);
+}
@@ -0,0 +1,36 @@
+mod common;
+use common::compile;
+
+#[test]
+fn immutable_bindings_can_not_be_mutated() {
+    let compiled = compile("mutation_immutable", "x 8 <- 1\nx <- 2\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("ImmutableMutation"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("mutated at"), "{}", compiled.reports);
+
+    let compiled = compile("mutation_immutable_increment", "x 8 <- 1\nx++\nret x\n");
+    assert!(compiled.reported("ImmutableMutation"), "{}", compiled.reports);
+
+    let compiled = compile("mutation_mutable", "'x 8 <- 1\nx <- 2\nx++\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+}
+
+#[test]
+fn only_places_can_be_mutated() {
+    let compiled = compile("mutation_not_a_place", "'x 8 <- 1\n3 <- x\nret x\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("InvalidAssignmentTarget"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("this is not an assignable place"), "{}", compiled.reports);
+}
+
+#[test]
+fn assigning_to_an_undeclared_name() {
+    let compiled = compile("mutation_undeclared", "y <- 2\nret 0\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UndefinedVariable"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("Cannot mutate undefined binding 'y'"),
+        "{}",
+        compiled.reports
+    );
+}
@@ -0,0 +1,18 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn undefined_names_are_reported() {
+    let compiled = compile("names_undefined", "ret y + 1\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UndefinedVariable"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("'y'"), "{}", compiled.reports);
+}
+
+#[test]
+fn labels_may_be_used_before_they_are_defined() {
+    let compiled = compile("names_forward_label", "x 8 <- later\nlater:\nret x\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("lea rax, [rel later]"), "{}", compiled.asm);
+}
@@ -0,0 +1,48 @@
+mod common;
+use common::compile;
+
+const PAIRS: &str = concat!(
+    "op + (a {8, 8}, b {8, 8}) {8, 8} {\n  ret {a.0 + b.0, a.1 + b.1}\n}\n",
+    "op + (a {8, 8}, n 8) {8, 8} {\n  ret {a.0 + n, a.1 + n}\n}\n",
+    "'p {8, 8}\n'q {8, 8}\n",
+);
+
+#[test]
+fn overloads_are_picked_by_operand_types() {
+    let compiled =
+        compile("operators_pair", &format!("{PAIRS}destr {{ x, y }} <- p + q\nret x + y\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.routine("_start").contains(&"    call op.add.0"), "{}", compiled.asm);
+
+    // an untyped literal goes into the scalar parameter
+    let compiled =
+        compile("operators_scalar", &format!("{PAIRS}destr {{ x, y }} <- p + 1\nret x + y\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.routine("_start").contains(&"    call op.add.1"), "{}", compiled.asm);
+    assert!(!compiled.routine("op.add.1").is_empty(), "{}", compiled.asm);
+}
+
+#[test]
+fn operators_without_an_overload() {
+    let compiled =
+        compile("operators_undefined", &format!("{PAIRS}destr {{ x, y }} <- p - q\nret x + y\n"));
+    assert!(!compiled.success);
+    assert!(compiled.reported("UndefinedOperator"), "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("`-` isn't defined for `{8, 8}` and `{8, 8}`"),
+        "{}",
+        compiled.reports
+    );
+}
+
+#[test]
+fn operators_need_a_struct_or_heap_operand() {
+    let compiled = compile("operators_scalar_only", "op + (a 8, b 8) 8 {\n  ret a\n}\nret 0\n");
+    assert!(compiled.reported("InvalidOperator"), "{}", compiled.reports);
+
+    let compiled = compile(
+        "operators_duplicate",
+        &format!("{PAIRS}op + (c {{8, 8}}, d {{8, 8}}) {{8, 8}} {{\n  ret c\n}}\nret 0\n"),
+    );
+    assert!(compiled.reported("DuplicateOperator"), "{}", compiled.reports);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{compile, Compiled};
+
+const DIVMOD: &str = "divmod (a 8, b 8) {8, 8} {\n  ret {a / b, a % b}\n}\n";
+
+// The lines from the first call to `divmod` on
+fn after_call(compiled: &Compiled) -> Vec<&str> {
+    compiled.asm.lines().map(str::trim).skip_while(|line| *line != "call divmod").collect()
+}
+
+#[test]
+fn into_a_binding() {
+    let compiled =
+        compile("returns_binding", &format!("{DIVMOD}q {{8, 8}} <- !divmod(7, 2)\nret 0\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let lines = after_call(&compiled);
+    assert_eq!(lines[1..3], ["push rax", "push rdx"]);
+    assert!(lines.contains(&"mov qword [rcx + 8], rax"), "{lines:#?}");
+    assert!(lines.contains(&"mov qword [rcx + 0], rax"), "{lines:#?}");
+}
+
+#[test]
+fn into_an_assignment() {
+    let compiled =
+        compile("returns_assignment", &format!("{DIVMOD}'p {{8, 8}}\np <- !divmod(8, 3)\nret 0\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+
+    let lines = after_call(&compiled);
+    assert_eq!(lines[1..3], ["push rax", "push rdx"]);
+    assert!(lines.contains(&"mov qword [rcx + 8], rax"), "{lines:#?}");
+    assert!(lines.contains(&"mov qword [rcx + 0], rax"), "{lines:#?}");
+}
+
+#[test]
+fn into_a_destructure() {
+    let compiled = compile(
+        "returns_destructure",
+        &format!("{DIVMOD}destr {{ d, m }} <- !divmod(5, 2)\nret d + m\n"),
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // the quotient comes back in rax and the remainder in rdx, each straight into its binding
+    let routine = compiled.routine("divmod");
+    assert!(routine.contains(&"    pop rax") && routine.contains(&"    pop rdx"), "{routine:#?}");
+    let lines = after_call(&compiled);
+    assert!(lines.contains(&"mov qword [rbp - 8], rax"), "{lines:#?}");
+    assert!(lines.contains(&"mov qword [rbp - 16], rdx"), "{lines:#?}");
+}
+
+#[test]
+fn the_declared_type_has_to_be_returned() {
+    let compiled = compile("returns_bare", "f (c 1) 8 {\n  c ? { ret }\n  ret 1\n}\nret !f(1)\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("InvalidReturn"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("expected a `8` to return"), "{}", compiled.reports);
+
+    let compiled = compile("returns_empty_body", "f () 8 {\n}\nret !f()\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("MissingReturn"), "{}", compiled.reports);
+
+    // a function without a return type can still leave early
+    let compiled = compile("returns_bare_unit", "f (c 1) {\n  c ? { ret }\n}\n!f(1)\n");
+    assert!(compiled.success, "{}", compiled.reports);
+}
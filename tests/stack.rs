@@ -0,0 +1,54 @@
+mod common;
+use common::compile_with;
+
+#[test]
+fn worst_case_depth_follows_calls() {
+    let compiled = compile_with(
+        "stack_calls",
+        concat!(
+            "extern abs(a 8) 8\n",
+            "leaf (a 8) 8 {\n  x 8 <- a * 2\n  ret x\n}\n",
+            "mid (a 8) 8 {\n  ret !leaf(a)\n}\n",
+            "outer (a 8) 8 {\n  ret @abs(a)\n}\n",
+            "ret !mid(1) + !outer(2)\n",
+        ),
+        &["--stack-usage"],
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("Label    Frame  Locals  Spills   Saved  Worst case\n"),
+        "{}",
+        compiled.reports
+    );
+    assert!(
+        compiled.reports.contains("leaf        40      16       8      16  40\n"),
+        "{}",
+        compiled.reports
+    );
+    // its own frame on top of the deepest callee's
+    assert!(
+        compiled.reports.contains("mid         40      16       8      16  80\n"),
+        "{}",
+        compiled.reports
+    );
+    assert!(compiled.reports.contains("(calls unknown `abs`)"), "{}", compiled.reports);
+}
+
+#[test]
+fn recursion_is_unbounded() {
+    let compiled = compile_with(
+        "stack_recursion",
+        concat!(
+            "even (n 8) 8 {\n  n = 0 ? { ret 1 }\n  ret !odd(n - 1)\n}\n",
+            "odd (n 8) 8 {\n  n = 0 ? { ret 0 }\n  ret !even(n - 1)\n}\n",
+            "ret !even(4)\n",
+        ),
+        &["--stack-usage"],
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(
+        compiled.reports.contains("unbounded (recursion: even -> odd -> even)"),
+        "{}",
+        compiled.reports
+    );
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::compile;
+
+#[test]
+fn field_names_are_identifiers() {
+    let compiled = compile("structs_numeric_field", "struct P { 8 8, y 8 }\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UnexpectedToken"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("Expected field name"), "{}", compiled.reports);
+    // columns count from 1
+    assert!(compiled.reports.contains("structs_numeric_field.shd:1:12\n"), "{}", compiled.reports);
+}
+
+#[test]
+fn duplicate_fields() {
+    let compiled = compile("structs_duplicate_field", "struct P { x 8, x 4 }\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("DuplicateField"), "{}", compiled.reports);
+}
+
+#[test]
+fn fields_are_laid_out_in_order() {
+    let compiled =
+        compile("structs_layout", "struct P { lo 4, hi 8 }\n'p P\np.hi <- 3\nret p.hi\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(compiled.asm.contains("add rax, 4"), "{}", compiled.asm);
+}
+
+#[test]
+fn misspelt_fields_suggest_the_closest() {
+    let compiled =
+        compile("structs_misspelt", "struct P { width 8, height 4 }\n'p P\nret p.widht\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UnknownField"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("HINT: Did you mean 'width'?"), "{}", compiled.reports);
+
+    // through a pointer too
+    let compiled = compile(
+        "structs_misspelt_pointer",
+        "struct P { width 8, height 4 }\nf (q [P]) 4 {\n  ret q.hieght\n}\nret !f(0)\n",
+    );
+    assert!(compiled.reports.contains("`[P]` has no field 'hieght'"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("HINT: Did you mean 'height'?"), "{}", compiled.reports);
+
+    // nothing close, so every field is listed
+    let compiled =
+        compile("structs_not_close", "struct P { width 8, height 4 }\n'p P\nret p.zzzzz\n");
+    assert!(
+        compiled.reports.contains("HINT: Its fields are width, height"),
+        "{}",
+        compiled.reports
+    );
+}
+
+#[test]
+fn fields_through_a_pointer_or_in_place() {
+    let compiled = compile(
+        "structs_pointer_access",
+        "struct P { width 8, height 4 }\nf (q [P]) 4 {\n  ret q.height\n}\n'p P\nret p.height + !f(0)\n",
+    );
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // the pointer is loaded, then offset
+    assert_eq!(compiled.routine("f")[5..8], [
+        "    mov rax, qword [rbp - 8]",
+        "    add rax, 8",
+        "    mov eax, dword [rax]",
+    ]);
+    // the struct is on the stack, so its address is taken instead
+    let start = compiled.routine("_start");
+    let offset = start.iter().position(|line| *line == "    add rax, 8").unwrap();
+    assert_eq!(start[offset - 1..offset + 2], [
+        "    lea rax, [rbp - 16]",
+        "    add rax, 8",
+        "    mov eax, dword [rax]",
+    ]);
+}
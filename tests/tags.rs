@@ -0,0 +1,55 @@
+mod common;
+use common::compile;
+
+#[test]
+fn names_pick_the_output_file() {
+    let dir = std::env::temp_dir().join("sharc-tests").join("tags_name");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("program.shd"), ":name \"hello\"\nret 0\n").unwrap();
+    let _ = std::fs::remove_file(dir.join("hello.asm"));
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_sharc"))
+        .args(["-f", "program.shd"])
+        .current_dir(&dir)
+        .env("NO_COLOR", "1")
+        .status()
+        .expect("Failed to run sharc");
+    assert!(status.success());
+    assert!(dir.join("hello.asm").exists());
+
+    // `-o` still wins
+    let compiled = compile("tags_name_output", ":name \"hello\"\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+    assert!(!compiled.asm.is_empty());
+}
+
+#[test]
+fn tags_are_checked() {
+    let compiled = compile("tags_duplicate", ":name \"hello\"\n:name \"again\"\nret 0\n");
+    assert!(compiled.reported("DuplicateTag"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("Tag ':name' is already set"), "{}", compiled.reports);
+
+    let compiled = compile("tags_arguments", ":name 5\nret 0\n");
+    assert!(compiled.reports.contains("expected a single string"), "{}", compiled.reports);
+
+    let compiled = compile("tags_nested", "f () {\n  :name \"x\"\n}\nret 0\n");
+    assert!(
+        compiled.reports.contains("Tags can only be used at the top level"),
+        "{}",
+        compiled.reports
+    );
+
+    // custom tags take anything
+    let compiled = compile("tags_custom", ":custom 1 2\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+}
+
+#[test]
+fn architectures_have_to_include_this_one() {
+    let compiled = compile("tags_arch_other", ":arch arm64 riscv\nret 0\n");
+    assert!(!compiled.success);
+    assert!(compiled.reported("UnsupportedArch"), "{}", compiled.reports);
+
+    let compiled = compile("tags_arch", ":arch x86_64 arm64\nret 0\n");
+    assert!(compiled.success, "{}", compiled.reports);
+}
@@ -0,0 +1,40 @@
+mod common;
+use common::compile;
+
+const FUNCTIONS: &str = "inc (a 8) 8 {\n  ret a + 1\n}\nadd (a 8, b 8) 8 {\n  ret a + b\n}\n";
+
+#[test]
+fn threaded_values_become_the_first_argument() {
+    let compiled = compile("threading_calls", &format!("{FUNCTIONS}ret 1 => !inc => !add(5)\n"));
+    assert!(compiled.success, "{}", compiled.reports);
+
+    // `!add(!inc(1), 5)`
+    let start = compiled.routine("_start");
+    let call = start.iter().position(|line| *line == "    call inc").unwrap();
+    assert_eq!(start[call - 2..call], ["    push rax", "    pop rdi"]);
+    assert_eq!(start[call + 1..call + 8], [
+        "    push rax",
+        "    mov rax, 5",
+        "    push rax",
+        "    pop rsi",
+        "    pop rdi",
+        "    call add",
+        "    mov rdi, rax",
+    ]);
+}
+
+#[test]
+fn threading_into_something_that_is_not_a_call() {
+    let compiled = compile("threading_literal", &format!("{FUNCTIONS}ret 1 => !inc => 5\n"));
+    assert!(!compiled.success);
+    assert!(compiled.reported("NotCallable"), "{}", compiled.reports);
+    // the link that's wrong, not the whole chain
+    assert!(compiled.reports.contains("threading_literal.shd:7:18\n"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("this is not a call"), "{}", compiled.reports);
+
+    // a bare name was likely meant to be called
+    let compiled = compile("threading_name", &format!("{FUNCTIONS}ret 1 => inc\n"));
+    assert!(compiled.reported("NotCallable"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("| ret 1 => !inc\n"), "{}", compiled.reports);
+    assert!(compiled.reports.contains("did you mean to call this?"), "{}", compiled.reports);
+}